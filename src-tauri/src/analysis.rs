@@ -0,0 +1,515 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use unicase::UniCase;
+use strsim::{jaro_winkler, normalized_levenshtein};
+use crate::resx;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum ComparisonStatus {
+    Identical,
+    Different,
+    MissingInTranslation,
+    MissingInDefault,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ComparisonEntry {
+    pub key: String,
+    pub default_value: String,
+    pub translated_value: String,
+    pub status: ComparisonStatus,
+}
+
+/// Compares every key of a translation against the default language, sorted by key.
+/// Missing values are reported as empty strings.
+pub fn compare_entries(default: &IndexMap<String, String>, translated: &IndexMap<String, String>) -> Vec<ComparisonEntry> {
+    let mut entries: Vec<ComparisonEntry> = default.iter().map(|(key, default_value)| {
+        let (translated_value, status) = match translated.get(key) {
+            Some(v) if v == default_value => (v.clone(), ComparisonStatus::Identical),
+            Some(v) => (v.clone(), ComparisonStatus::Different),
+            None => (String::new(), ComparisonStatus::MissingInTranslation),
+        };
+        ComparisonEntry { key: key.clone(), default_value: default_value.clone(), translated_value, status }
+    }).collect();
+
+    entries.extend(translated.iter().filter(|(key, _)| !default.contains_key(*key)).map(|(key, value)| ComparisonEntry {
+        key: key.clone(),
+        default_value: String::new(),
+        translated_value: value.clone(),
+        status: ComparisonStatus::MissingInDefault,
+    }));
+
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    entries
+}
+
+pub fn compare_file_with_default(default_file: &Path, translated_file: &Path) -> Result<Vec<ComparisonEntry>> {
+    let default = resx::parse_resx(default_file)?.entries;
+    let translated = resx::parse_resx(translated_file)?.entries;
+    Ok(compare_entries(&default, &translated))
+}
+
+/// Returns the keys whose value equals `value`, sorted. A case-insensitive match uses Unicode
+/// case folding, so e.g. "STRASSE" matches "straße".
+pub fn find_keys_by_value(entries: &IndexMap<String, String>, value: &str, case_sensitive: bool) -> Vec<String> {
+    let mut keys: Vec<String> = entries.iter()
+        .filter(|(_, v)| if case_sensitive { v.as_str() == value } else { UniCase::new(v.as_str()) == UniCase::new(value) })
+        .map(|(k, _)| k.clone())
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HtmlTagError {
+    pub key: String,
+    pub lang: String,
+    pub expected_tags: Vec<String>,
+    pub actual_tags: Vec<String>,
+}
+
+/// Returns the names of the HTML tags in `value`, sorted, with closing tags prefixed by `/`.
+/// Anything that looks like `<name` or `</name` counts as a tag; attributes are ignored.
+pub fn extract_html_tags(value: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let (closing, name_start) = match rest.strip_prefix('/') {
+            Some(r) => (true, r),
+            None => (false, rest),
+        };
+        let name_len = name_start.find(|c: char| !c.is_ascii_alphanumeric() && c != '-').unwrap_or(name_start.len());
+        let name = &name_start[..name_len];
+        if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let name = name.to_ascii_lowercase();
+            tags.push(if closing { format!("/{}", name) } else { name });
+        }
+    }
+    tags.sort();
+    tags
+}
+
+/// Compares the HTML tags of every translated value with the default value of the same key.
+/// Keys missing from either side and empty translations are not checked.
+pub fn check_html_tags(default: &IndexMap<String, String>, translated: &IndexMap<String, String>, lang: &str) -> Vec<HtmlTagError> {
+    let mut errors: Vec<HtmlTagError> = translated.iter()
+        .filter(|(_, value)| !value.is_empty())
+        .filter_map(|(key, value)| {
+            let expected_tags = extract_html_tags(default.get(key)?);
+            let actual_tags = extract_html_tags(value);
+            (expected_tags != actual_tags).then(|| HtmlTagError { key: key.clone(), lang: lang.to_string(), expected_tags, actual_tags })
+        })
+        .collect();
+    errors.sort_by(|a, b| a.key.cmp(&b.key));
+    errors
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SimilarityWarning {
+    pub key: String,
+    pub lang: String,
+    pub similarity_score: f32,
+}
+
+/// Flags translated values that are at least `threshold` similar (normalized Levenshtein,
+/// 1.0 = identical) to the default value, i.e. probably copied rather than translated.
+/// Empty values on either side are not checked. Sorted by key.
+pub fn find_similar_to_default(default: &IndexMap<String, String>, translated: &IndexMap<String, String>, lang: &str, threshold: f32) -> Vec<SimilarityWarning> {
+    let mut warnings: Vec<SimilarityWarning> = translated.iter()
+        .filter(|(_, value)| !value.is_empty())
+        .filter_map(|(key, value)| {
+            let default_value = default.get(key).filter(|v| !v.is_empty())?;
+            let similarity_score = normalized_levenshtein(default_value, value) as f32;
+            (similarity_score >= threshold).then(|| SimilarityWarning { key: key.clone(), lang: lang.to_string(), similarity_score })
+        })
+        .collect();
+    warnings.sort_by(|a, b| a.key.cmp(&b.key));
+    warnings
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct KeySuggestion {
+    pub key: String,
+    /// Jaro-Winkler similarity to the query, from 0 to 1.
+    pub similarity: f32,
+}
+
+/// Scores every key against `query` and returns the `max_suggestions` most similar ones,
+/// best first. Ties are sorted by key.
+pub fn suggest_similar_keys(keys: &[String], query: &str, max_suggestions: usize) -> Vec<KeySuggestion> {
+    let mut suggestions: Vec<KeySuggestion> = keys.iter()
+        .map(|key| KeySuggestion { key: key.clone(), similarity: jaro_winkler(query, key) as f32 })
+        .collect();
+    suggestions.sort_by(|a, b| b.similarity.total_cmp(&a.similarity).then_with(|| a.key.cmp(&b.key)));
+    suggestions.truncate(max_suggestions);
+    suggestions
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MissingTranslation {
+    pub key: String,
+    /// Languages in which the key is missing or empty, sorted.
+    pub missing_in: Vec<String>,
+}
+
+/// Lists the keys with a non-empty default value that are missing or empty in any of the
+/// `translations` (lang, entries), sorted by key.
+pub fn find_missing_translations(default: &IndexMap<String, String>, translations: &[(String, IndexMap<String, String>)]) -> Vec<MissingTranslation> {
+    let mut missing: Vec<MissingTranslation> = default.iter()
+        .filter(|(_, value)| !value.is_empty())
+        .filter_map(|(key, _)| {
+            let mut missing_in: Vec<String> = translations.iter()
+                .filter(|(_, entries)| entries.get(key).is_none_or(|v| v.is_empty()))
+                .map(|(lang, _)| lang.clone())
+                .collect();
+            missing_in.sort();
+            (!missing_in.is_empty()).then(|| MissingTranslation { key: key.clone(), missing_in })
+        })
+        .collect();
+    missing.sort_by(|a, b| a.key.cmp(&b.key));
+    missing
+}
+
+/// Counts the missing translations per language.
+pub fn count_missing_by_language<'a>(missing: impl IntoIterator<Item = &'a MissingTranslation>) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for lang in missing.into_iter().flat_map(|m| &m.missing_in) {
+        *counts.entry(lang.clone()).or_default() += 1;
+    }
+    counts
+}
+
+/// How many translations were completed per language between two missing counts. Negative if
+/// more are missing now, e.g. because keys were added or a language appeared since the baseline.
+pub fn translation_velocity(baseline: &HashMap<String, usize>, current: &HashMap<String, usize>) -> HashMap<String, i64> {
+    baseline.keys().chain(current.keys())
+        .map(|lang| {
+            let before = baseline.get(lang).copied().unwrap_or(0) as i64;
+            let now = current.get(lang).copied().unwrap_or(0) as i64;
+            (lang.clone(), before - now)
+        })
+        .collect()
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GroupStats {
+    pub group_name: String,
+    pub directory: String,
+    /// Number of distinct keys across all files of the group.
+    pub total_keys: usize,
+    /// Percentage (0-100) of `total_keys` with a non-empty value, per language.
+    pub completeness: BTreeMap<String, f32>,
+}
+
+pub fn group_stats(group_name: &str, directory: &str, files: &[(String, IndexMap<String, String>)]) -> GroupStats {
+    let all_keys: HashSet<&String> = files.iter().flat_map(|(_, entries)| entries.keys()).collect();
+    let total_keys = all_keys.len();
+    let completeness = files.iter().map(|(lang, entries)| {
+        let filled = entries.values().filter(|v| !v.is_empty()).count();
+        let percent = if total_keys == 0 { 100.0 } else { filled as f32 * 100.0 / total_keys as f32 };
+        (lang.clone(), percent)
+    }).collect();
+    GroupStats { group_name: group_name.to_string(), directory: directory.to_string(), total_keys, completeness }
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DiffStatistics {
+    pub keys_added: usize,
+    pub keys_removed: usize,
+    pub keys_modified: usize,
+    pub keys_unchanged: usize,
+    /// Characters of added values, and of the new values of modified keys.
+    pub total_characters_added: usize,
+    /// Characters of removed values, and of the old values of modified keys.
+    pub total_characters_removed: usize,
+}
+
+/// Counts the changes between two versions of a group, given as (lang, entries) per file.
+/// Files are matched by language and every key counts once per language; a language only
+/// present in one version counts as all of its keys added or removed.
+pub fn diff_statistics(before: &[(String, IndexMap<String, String>)], after: &[(String, IndexMap<String, String>)]) -> DiffStatistics {
+    let empty = IndexMap::new();
+    let languages: BTreeSet<&String> = before.iter().chain(after).map(|(lang, _)| lang).collect();
+
+    let mut stats = DiffStatistics::default();
+    for lang in languages {
+        let old = before.iter().find(|(l, _)| l == lang).map_or(&empty, |(_, entries)| entries);
+        let new = after.iter().find(|(l, _)| l == lang).map_or(&empty, |(_, entries)| entries);
+        let changes = resx::diff_entries(old, new);
+        stats.keys_unchanged += old.len() - changes.iter().filter(|c| !matches!(c, resx::ResxChange::Added { .. })).count();
+        for change in changes {
+            match change {
+                resx::ResxChange::Added { value, .. } => {
+                    stats.keys_added += 1;
+                    stats.total_characters_added += value.chars().count();
+                }
+                resx::ResxChange::Removed { value, .. } => {
+                    stats.keys_removed += 1;
+                    stats.total_characters_removed += value.chars().count();
+                }
+                resx::ResxChange::Modified { old_value, new_value, .. } => {
+                    stats.keys_modified += 1;
+                    stats.total_characters_removed += old_value.chars().count();
+                    stats.total_characters_added += new_value.chars().count();
+                }
+                // diff_entries doesn't detect renames
+                resx::ResxChange::Renamed { .. } => {}
+            }
+        }
+    }
+    stats
+}
+
+/// What the properties of a generated TypeScript constants object hold.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TsExportMode {
+    /// The full resx key, for use as an i18n message id.
+    KeysAsValues,
+    /// The value in the exported language.
+    ActualValues,
+}
+
+enum TsNode {
+    Leaf(String),
+    Object(Vec<(String, TsNode)>),
+}
+
+fn insert_ts_node(children: &mut Vec<(String, TsNode)>, path: &[&str], key: &str, value: String) -> Result<()> {
+    let (name, rest) = path.split_first().ok_or_else(|| anyhow::anyhow!("Key '{}' has an empty part", key))?;
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Key '{}' has an empty part", key));
+    }
+    let existing = children.iter_mut().find(|(n, _)| n == name);
+    match (existing, rest.is_empty()) {
+        (None, true) => children.push((name.to_string(), TsNode::Leaf(value))),
+        (None, false) => {
+            let mut grandchildren = Vec::new();
+            insert_ts_node(&mut grandchildren, rest, key, value)?;
+            children.push((name.to_string(), TsNode::Object(grandchildren)));
+        }
+        (Some((_, TsNode::Object(grandchildren))), false) => insert_ts_node(grandchildren, rest, key, value)?,
+        // "A" and "A.B" can't both be properties
+        (Some(_), _) => return Err(anyhow::anyhow!("Key '{}' conflicts with another key sharing its prefix", key)),
+    }
+    Ok(())
+}
+
+fn is_ts_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+fn write_ts_object(out: &mut String, children: &[(String, TsNode)], depth: usize) {
+    out.push_str("{\n");
+    for (name, node) in children {
+        out.push_str(&"    ".repeat(depth + 1));
+        if is_ts_identifier(name) {
+            out.push_str(name);
+        } else {
+            out.push_str(&serde_json::to_string(name).unwrap_or_default());
+        }
+        out.push_str(": ");
+        match node {
+            TsNode::Leaf(value) => out.push_str(&serde_json::to_string(value).unwrap_or_default()),
+            TsNode::Object(grandchildren) => write_ts_object(out, grandchildren, depth + 1),
+        }
+        out.push_str(",\n");
+    }
+    out.push_str(&"    ".repeat(depth));
+    out.push('}');
+}
+
+/// Generates a TypeScript module exporting the (key, value) `entries` as a `const` object called
+/// `name`, with dots in keys turned into nested objects ("Button.Save" becomes `Button: { Save: ... }`),
+/// and optionally a `<name>Key` union type of all keys.
+pub fn ts_consts_module(name: &str, entries: &[(String, String)], mode: TsExportMode, include_key_type: bool) -> Result<String> {
+    if !is_ts_identifier(name) {
+        return Err(anyhow::anyhow!("'{}' is not a valid TypeScript identifier", name));
+    }
+    let mut root = Vec::new();
+    for (key, value) in entries {
+        let value = match mode {
+            TsExportMode::KeysAsValues => key.clone(),
+            TsExportMode::ActualValues => value.clone(),
+        };
+        insert_ts_node(&mut root, &key.split('.').collect::<Vec<_>>(), key, value)?;
+    }
+
+    let mut out = String::from("// Generated by EasyResX. Changes will be lost when the file is exported again.\n\n");
+    out.push_str(&format!("export const {} = ", name));
+    write_ts_object(&mut out, &root, 0);
+    out.push_str(" as const;\n");
+    if include_key_type {
+        let keys: Vec<String> = entries.iter().map(|(key, _)| serde_json::to_string(key).unwrap_or_default()).collect();
+        let union = if keys.is_empty() { "never".to_string() } else { keys.join(" | ") };
+        out.push_str(&format!("\nexport type {}Key = {};\n", name, union));
+    }
+    Ok(out)
+}
+
+/// Writes one row per group with a completeness column for every language found in any group.
+/// Languages a group doesn't have are left empty.
+pub fn write_group_stats_csv(stats: &[GroupStats], output_path: &Path) -> Result<()> {
+    let languages: BTreeSet<&String> = stats.iter().flat_map(|s| s.completeness.keys()).collect();
+    let mut writer = csv::Writer::from_path(output_path)?;
+
+    let mut header = vec!["group_name", "directory", "total_keys"];
+    header.extend(languages.iter().map(|lang| lang.as_str()));
+    writer.write_record(&header)?;
+
+    for group in stats {
+        let mut record = vec![group.group_name.clone(), group.directory.clone(), group.total_keys.to_string()];
+        record.extend(languages.iter().map(|lang| {
+            group.completeness.get(*lang).map_or_else(String::new, |percent| format!("{:.1}", percent))
+        }));
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> IndexMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_find_keys_by_value() {
+        let entries = map(&[("Save", "Speichern"), ("Store", "speichern"), ("Street", "Straße"), ("Exit", "Beenden")]);
+
+        assert_eq!(find_keys_by_value(&entries, "Speichern", true), vec!["Save"]);
+        assert_eq!(find_keys_by_value(&entries, "SPEICHERN", false), vec!["Save", "Store"]);
+        assert_eq!(find_keys_by_value(&entries, "STRASSE", false), vec!["Street"]);
+        assert!(find_keys_by_value(&entries, "Abbrechen", false).is_empty());
+    }
+
+    #[test]
+    fn test_check_html_tags() {
+        assert_eq!(extract_html_tags("Click <a href='x'>here</a> or <BR/> 1 < 2"), vec!["/a", "a", "br"]);
+
+        let default = map(&[("Link", "Click <a href='x'>here</a>"), ("Bold", "<b>Hi</b>"), ("Plain", "Hi")]);
+        let translated = map(&[("Link", "Klicken Sie <a href='x'>hier</a>"), ("Bold", "Hallo"), ("Plain", ""), ("Extra", "<i>x</i>")]);
+        assert_eq!(check_html_tags(&default, &translated, "de"), vec![HtmlTagError {
+            key: "Bold".to_string(),
+            lang: "de".to_string(),
+            expected_tags: vec!["/b".to_string(), "b".to_string()],
+            actual_tags: vec![],
+        }]);
+    }
+
+    #[test]
+    fn test_find_similar_to_default() {
+        let default = map(&[("Copied", "Save your changes"), ("Translated", "Save your changes"), ("Same", "OK"), ("Empty", "Exit")]);
+        let translated = map(&[("Copied", "Save your changes."), ("Translated", "Änderungen speichern"), ("Same", "OK"), ("Empty", "")]);
+
+        let flagged: Vec<String> = find_similar_to_default(&default, &translated, "de", 0.9)
+            .into_iter().map(|w| w.key).collect();
+        assert_eq!(flagged, vec!["Copied", "Same"]);
+    }
+
+    #[test]
+    fn test_compare_entries_statuses() {
+        let default = map(&[("Same", "OK"), ("Diff", "Save"), ("OnlyDefault", "Exit")]);
+        let translated = map(&[("Same", "OK"), ("Diff", "Speichern"), ("OnlyTranslated", "Extra")]);
+
+        let statuses: Vec<(String, ComparisonStatus)> = compare_entries(&default, &translated)
+            .into_iter().map(|e| (e.key, e.status)).collect();
+        assert_eq!(statuses, vec![
+            ("Diff".to_string(), ComparisonStatus::Different),
+            ("OnlyDefault".to_string(), ComparisonStatus::MissingInTranslation),
+            ("OnlyTranslated".to_string(), ComparisonStatus::MissingInDefault),
+            ("Same".to_string(), ComparisonStatus::Identical),
+        ]);
+    }
+
+    #[test]
+    fn test_suggest_similar_keys() {
+        let keys: Vec<String> = ["Button.Save", "Button.Cancel", "Title"].iter().map(|k| k.to_string()).collect();
+        let suggestions = suggest_similar_keys(&keys, "Buton.Save", 2);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].key, "Button.Save");
+        assert!(suggestions[0].similarity > 0.9);
+        assert_eq!(suggestions[1].key, "Button.Cancel");
+    }
+
+    #[test]
+    fn test_find_missing_translations() {
+        let default = map(&[("A", "a"), ("B", "b"), ("Empty", "")]);
+        let translations = vec![
+            ("fr".to_string(), map(&[("A", "a-fr"), ("B", "")])),
+            ("de".to_string(), map(&[("A", "a-de")])),
+        ];
+        assert_eq!(find_missing_translations(&default, &translations), vec![MissingTranslation {
+            key: "B".to_string(),
+            missing_in: vec!["de".to_string(), "fr".to_string()],
+        }]);
+    }
+
+    #[test]
+    fn test_translation_velocity() {
+        let baseline = HashMap::from([("de".to_string(), 10), ("fr".to_string(), 4)]);
+        let current = HashMap::from([("de".to_string(), 3), ("fr".to_string(), 4), ("ja".to_string(), 2)]);
+        let velocity = translation_velocity(&baseline, &current);
+        assert_eq!(velocity.len(), 3);
+        assert_eq!(velocity["de"], 7);
+        assert_eq!(velocity["fr"], 0);
+        assert_eq!(velocity["ja"], -2);
+    }
+
+    #[test]
+    fn test_diff_statistics() {
+        let before = vec![
+            ("default".to_string(), map(&[("A", "same"), ("B", "old"), ("C", "gone")])),
+            ("fr".to_string(), map(&[("A", "même")])),
+        ];
+        let after = vec![
+            ("default".to_string(), map(&[("A", "same"), ("B", "newer"), ("D", "added")])),
+            ("de".to_string(), map(&[("A", "gleich")])),
+        ];
+        assert_eq!(diff_statistics(&before, &after), DiffStatistics {
+            keys_added: 2,
+            keys_removed: 2,
+            keys_modified: 1,
+            keys_unchanged: 1,
+            total_characters_added: 5 + 5 + 6,
+            total_characters_removed: 3 + 4 + 4,
+        });
+    }
+
+    #[test]
+    fn test_ts_consts_module() -> Result<()> {
+        let entries: Vec<(String, String)> = [("Button.Save", "Save"), ("Button.Cancel", "Cancel \"now\""), ("my-key", "x")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let module = ts_consts_module("Strings", &entries, TsExportMode::KeysAsValues, true)?;
+        assert!(module.contains("export const Strings = {\n    Button: {\n        Save: \"Button.Save\",\n        Cancel: \"Button.Cancel\",\n    },\n    \"my-key\": \"my-key\",\n} as const;\n"));
+        assert!(module.ends_with("export type StringsKey = \"Button.Save\" | \"Button.Cancel\" | \"my-key\";\n"));
+
+        let module = ts_consts_module("Strings", &entries, TsExportMode::ActualValues, false)?;
+        assert!(module.contains("Cancel: \"Cancel \\\"now\\\"\","));
+        assert!(!module.contains("export type"));
+
+        let conflicting = vec![("A".to_string(), String::new()), ("A.B".to_string(), String::new())];
+        assert!(ts_consts_module("Strings", &conflicting, TsExportMode::KeysAsValues, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_stats_completeness() {
+        let files = vec![
+            ("default".to_string(), map(&[("A", "a"), ("B", "b"), ("C", "c"), ("D", "d")])),
+            ("de".to_string(), map(&[("A", "a"), ("B", ""), ("E", "e")])),
+        ];
+        let stats = group_stats("Strings", "/p", &files);
+        assert_eq!(stats.total_keys, 5);
+        assert_eq!(stats.completeness["default"], 80.0);
+        assert_eq!(stats.completeness["de"], 40.0);
+    }
+}
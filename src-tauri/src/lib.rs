@@ -1,202 +1,1370 @@
+mod analysis;
+mod oplog;
+mod project;
 mod resx;
+mod scan;
 mod settings;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::collections::{HashMap, HashSet};
-use std::sync::Mutex;
-use walkdir::WalkDir;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use tauri::{AppHandle, Emitter, Manager};
-use settings::AppSettings;
+use tauri::{AppHandle, Emitter, Manager, State};
+use walkdir::WalkDir;
+use oplog::OperationLog;
+use scan::ScanOptions;
+use settings::{AppSettings, SavedGroup};
+
+/// A file watcher that remembers what it watches, so it can unwatch everything explicitly
+/// when dropped instead of relying on the platform backend to release its handles.
+struct ResxFileWatcher {
+    watcher: RecommendedWatcher,
+    watched: Vec<PathBuf>,
+}
+
+impl ResxFileWatcher {
+    fn watch(&mut self, path: &Path, mode: RecursiveMode) -> notify::Result<()> {
+        self.watcher.watch(path, mode)?;
+        self.watched.push(path.to_path_buf());
+        Ok(())
+    }
+
+    fn unwatch_all(&mut self) {
+        for path in self.watched.drain(..) {
+            if let Err(e) = self.watcher.unwatch(&path) {
+                tracing::warn!("Failed to unwatch {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+impl Drop for ResxFileWatcher {
+    fn drop(&mut self) {
+        self.unwatch_all();
+    }
+}
 
 struct WatcherState {
-    watcher: Mutex<Option<RecommendedWatcher>>,
+    watcher: Mutex<Option<ResxFileWatcher>>,
+}
+
+impl Drop for WatcherState {
+    // Drops the watcher here rather than whenever the managed state happens to be torn down
+    fn drop(&mut self) {
+        let watcher = self.watcher.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        watcher.take();
+    }
+}
+
+/// Contents of each file as it was when first loaded, or when its group was loaded with
+/// `reset_snapshots`, keyed by path. Used to summarize what has changed since the group was opened.
+struct SnapshotState {
+    snapshots: Mutex<HashMap<String, IndexMap<String, String>>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct ResxFile {
-    path: String,
+    path: String, // Relative to the group's directory when scanned
     lang: String, // "default" or "en-US"
+    #[serde(default)]
+    encoding: Option<resx::FileEncoding>,
+    #[serde(default)]
+    last_modified_ms: u64, // Milliseconds since the Unix epoch, 0 if unknown
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone)]
 struct ResxGroup {
+    id: String, // "<directory>::<name>", stable across scans
     name: String,
     directory: String,
+    base_directory: String, // Root of the scan that found this group
     files: Vec<ResxFile>,
+    has_default_file: bool,
+}
+
+impl ResxGroup {
+    /// Absolute path of one of the group's files.
+    fn file_path(&self, file: &ResxFile) -> PathBuf {
+        Path::new(&self.directory).join(&file.path)
+    }
+}
+
+/// Resolves a file path passed to a command. Files of a scanned group are passed with the
+/// group's `directory` and their path relative to it; a path given without a directory, or an
+/// absolute one, is used as it is.
+fn resolve_path(directory: Option<&str>, path: &str) -> String {
+    match directory {
+        Some(directory) => Path::new(directory).join(path).to_string_lossy().to_string(),
+        None => path.to_string(),
+    }
 }
 
 #[derive(Serialize)]
 struct RowData {
     key: String,
     values: HashMap<String, String>, // Lang -> Value
+    /// All non-empty values are the same, which usually means the key isn't translated yet.
+    /// Empty values are ignored; always false for keys with fewer than two non-empty values.
+    is_consistent: bool,
+    /// The resource type for non-string entries (e.g. images), whose values are truncated base64.
+    type_attr: Option<String>,
+    /// Paths of the files that contain this key.
+    source_files: HashSet<String>,
+    /// Lang -> the key's `<comment>`, for every language whose file contains the key.
+    comments: HashMap<String, Option<String>>,
+}
+
+#[derive(Serialize)]
+struct ScanResult {
+    groups: Vec<ResxGroup>,
+    scan_duration_ms: u64,
+    files_examined: usize, // All files walked, not only .resx ones
+    /// Paths that could not be read, with the error; the scan skipped them.
+    scan_errors: Vec<(String, String)>,
 }
 
+/// Groups are sorted by name unless `options.sort_by` says otherwise.
 #[tauri::command]
-fn scan_directory(path: &str) -> Vec<ResxGroup> {
-    let mut groups: HashMap<String, ResxGroup> = HashMap::new();
-
-    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("resx") {
-            let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
-            let parent = path.parent().unwrap_or(Path::new("")).to_string_lossy().to_string();
-            
-            // Heuristic: Split by dot. Last part is lang if short, else default.
-            let parts: Vec<&str> = file_stem.split('.').collect();
-            let (group_name, lang) = if parts.len() > 1 {
-                 let potential_lang = parts.last().unwrap();
-                 // Valid lang codes are usually 2-3 chars or 5 chars (en, en-US)
-                 // Some are longer "zh-Hans", "az-Latn-AZ"
-                 if potential_lang.len() <= 10 && potential_lang.chars().next().unwrap_or(' ').is_ascii_alphabetic() {
-                     (parts[..parts.len()-1].join("."), potential_lang.to_string())
-                 } else {
-                     (file_stem.to_string(), "default".to_string())
-                 }
-            } else {
-                (file_stem.to_string(), "default".to_string())
-            };
+fn scan_directory(path: &str, options: Option<ScanOptions>) -> Result<ScanResult, String> {
+    let root = scan::resolve_root(path)?;
+    let start = Instant::now();
+    let options = options.unwrap_or_default();
+    let outcome = scan::scan_groups(&root, &options, |_, _| {});
+    Ok(ScanResult {
+        groups: outcome.groups,
+        scan_duration_ms: start.elapsed().as_millis() as u64,
+        files_examined: outcome.files_examined,
+        scan_errors: outcome.errors,
+    })
+}
 
-            let group_key = format!("{}::{}", parent, group_name);
+#[derive(Serialize, Clone)]
+struct ScanProgress {
+    group: Option<ResxGroup>,
+    files_scanned: usize,
+    is_complete: bool,
+    /// Paths the scan could not read; only set on the final event.
+    scan_errors: Vec<(String, String)>,
+}
 
-            groups.entry(group_key.clone()).or_insert(ResxGroup {
-                name: group_name,
-                directory: parent.clone(),
-                files: Vec::new(),
-            }).files.push(ResxFile {
-                path: path.to_string_lossy().to_string(),
-                lang,
+/// Scans in the background, emitting a `scan-progress` event for each group as it is found
+/// and a final event with `is_complete: true`, no group and any scan errors.
+#[tauri::command]
+fn scan_directory_streaming(app: AppHandle, path: String, options: ScanOptions) -> Result<(), String> {
+    let root = scan::resolve_root(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let outcome = scan::scan_groups(&root, &options, |group, files_scanned| {
+            let _ = app.emit("scan-progress", ScanProgress {
+                group: Some(group.clone()),
+                files_scanned,
+                is_complete: false,
+                scan_errors: Vec::new(),
             });
-        }
-    }
-    
-    // Sort files in groups: default first, then alphabetical
-    for group in groups.values_mut() {
-        group.files.sort_by(|a, b| {
-            if a.lang == "default" { std::cmp::Ordering::Less }
-            else if b.lang == "default" { std::cmp::Ordering::Greater }
-            else { a.lang.cmp(&b.lang) }
         });
+        let _ = app.emit("scan-progress", ScanProgress {
+            group: None,
+            files_scanned: outcome.files_examined,
+            is_complete: true,
+            scan_errors: outcome.errors,
+        });
+    });
+    Ok(())
+}
+
+/// Rescans `path` and reports only the groups that differ from `previous_scan`. The whole
+/// tree is walked again; only files modified since `previous_scan` are read. `options` should
+/// be those of the previous scan, or groups it left out are reported as added.
+#[tauri::command]
+fn scan_directory_incremental(path: &str, previous_scan: Vec<ResxGroup>, options: Option<ScanOptions>) -> Result<scan::ScanDiff, String> {
+    let root = scan::resolve_root(path)?;
+    Ok(scan::rescan(&root, &options.unwrap_or_default(), &previous_scan))
+}
+
+/// Lists every resx file below `path` without grouping them. Unlike in scanned groups,
+/// the paths are absolute since the files don't share a directory.
+#[tauri::command]
+fn list_resxfiles_in_directory(path: &str, options: ScanOptions) -> Result<Vec<ResxFile>, String> {
+    let root = scan::resolve_root(path)?;
+    let groups = scan::scan_groups(&root, &options, |_, _| {}).groups;
+    Ok(groups.iter()
+        .flat_map(|group| group.files.iter().map(move |file| ResxFile {
+            path: group.file_path(file).to_string_lossy().to_string(),
+            ..file.clone()
+        }))
+        .collect())
+}
+
+/// Lists the .NET projects below `path` with their resource directories, so the frontend
+/// can offer to open one when a solution directory was chosen.
+#[tauri::command]
+fn auto_detect_project_structure(path: &str) -> Result<Vec<scan::ProjectSuggestion>, String> {
+    let root = scan::resolve_root(path)?;
+    Ok(scan::detect_projects(&root))
+}
+
+#[derive(Serialize)]
+struct PagedRowData {
+    rows: Vec<RowData>,
+    total_keys: usize,
+    page: usize,
+    page_count: usize,
+}
+
+#[derive(Serialize)]
+struct LoadedGroup {
+    rows: Vec<RowData>,
+    /// Encoding of each file, by its path in the group. Files whose encoding can't be read are left out.
+    encodings: HashMap<String, resx::FileEncoding>,
+}
+
+/// Detects the encoding of every file of `group`, at load time rather than when it was scanned.
+fn group_encodings(group: &ResxGroup) -> HashMap<String, resx::FileEncoding> {
+    group.files.iter()
+        .filter_map(|file| Some((file.path.clone(), resx::detect_encoding(&group.file_path(file)).ok()?)))
+        .collect()
+}
+
+/// Each file may appear in `files` only once; a path listed twice is rejected, since its keys
+/// would otherwise be loaded again, possibly under another language.
+#[tauri::command]
+fn load_group(app: AppHandle, snapshots: State<'_, SnapshotState>, group: ResxGroup, options: Option<LoadGroupOptions>) -> Result<LoadedGroup, String> {
+    let rows = load_rows(&app, &snapshots, &group, &options.unwrap_or_default())?;
+    Ok(LoadedGroup { rows, encodings: group_encodings(&group) })
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LoadGroupOptions {
+    /// Values longer than this many characters are cut off and end in "...";
+    /// `get_full_value` returns the complete value.
+    max_value_length: Option<usize>,
+    /// Keep keys in the order they appear in the files (the first file's order, then keys only
+    /// found in later files) instead of sorting them by name.
+    preserve_order: bool,
+    /// Take the files' current contents as the baseline for `get_change_summary`, e.g. when the
+    /// group is opened again. Otherwise only a file's first load sets the baseline.
+    reset_snapshots: bool,
+}
+
+/// Shortens `value` to `max` characters, returning whether anything was cut off.
+fn truncate_chars(value: &mut String, max: usize) -> bool {
+    match value.char_indices().nth(max) {
+        Some((cut, _)) => {
+            value.truncate(cut);
+            true
+        }
+        None => false,
     }
+}
 
-    let mut result: Vec<ResxGroup> = groups.into_values().collect();
-    result.sort_by(|a, b| a.name.cmp(&b.name));
-    result
+/// The rows of `group` as `load_group` returns them, with `options` applied.
+fn load_rows(app: &AppHandle, snapshots: &SnapshotState, group: &ResxGroup, options: &LoadGroupOptions) -> Result<Vec<RowData>, String> {
+    let mut rows = build_rows(snapshots, group, &settings::load_settings(app), options.preserve_order, options.reset_snapshots)?;
+    if let Some(max) = options.max_value_length {
+        for value in rows.iter_mut().flat_map(|row| row.values.values_mut()) {
+            if truncate_chars(value, max) {
+                value.push_str("...");
+            }
+        }
+    }
+    Ok(rows)
 }
 
+/// Like `load_group`, but only returns the rows of one page (zero-based).
+/// Rows are in the same order as `load_group`'s with the same `options`, so pages are
+/// stable across requests as long as the files don't change.
 #[tauri::command]
-fn load_group(files: Vec<ResxFile>) -> Result<Vec<RowData>, String> {
+fn load_group_paged(app: AppHandle, snapshots: State<'_, SnapshotState>, group: ResxGroup, page: usize, page_size: usize, options: Option<LoadGroupOptions>) -> Result<PagedRowData, String> {
+    if page_size == 0 {
+        return Err("Page size must be greater than 0".to_string());
+    }
+
+    let rows = load_rows(&app, &snapshots, &group, &options.unwrap_or_default())?;
+    let total_keys = rows.len();
+    let page_count = total_keys.div_ceil(page_size);
+    let rows = rows.into_iter().skip(page * page_size).take(page_size).collect();
+
+    Ok(PagedRowData { rows, total_keys, page, page_count })
+}
+
+/// How many characters of a binary entry's base64 content are shown.
+const BINARY_PREVIEW_LEN: usize = 64;
+
+/// Non-string entries (those with a `type` attribute) are left out unless `show_binary_entries` is set,
+/// and so are keys matching one of the `hidden_key_patterns`.
+/// Rows are sorted by key unless `preserve_order` is set, in which case they follow file order.
+/// `source_files` lists the files by their path in the group, relative to its directory.
+fn build_rows(snapshots: &SnapshotState, group: &ResxGroup, settings: &AppSettings, preserve_order: bool, reset_snapshots: bool) -> Result<Vec<RowData>, String> {
+    let mut seen_paths = HashSet::new();
+    if let Some(duplicate) = group.files.iter().find(|f| !seen_paths.insert(f.path.as_str())) {
+        return Err(format!("File '{}' was passed more than once", duplicate.path));
+    }
+    let hidden_keys = settings::hidden_key_regexes(&settings.hidden_key_patterns)?;
     let mut key_map: HashMap<String, HashMap<String, String>> = HashMap::new();
-    let mut all_keys: HashSet<String> = HashSet::new();
+    let mut key_types: HashMap<String, String> = HashMap::new();
+    let mut key_sources: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut key_comments: HashMap<String, HashMap<String, Option<String>>> = HashMap::new();
+    let mut all_keys: IndexSet<String> = IndexSet::new();
+    let mut snapshots = snapshots.snapshots.lock().map_err(|e| e.to_string())?;
 
-    for file in files {
+    for file in &group.files {
+        let path = group.file_path(file);
         // We ignore errors for individual files to show partial data, or we could fail.
         // Let's log error and continue.
-        if let Ok(parsed) = resx::parse_resx(Path::new(&file.path)) {
-            for (k, v) in parsed {
-                all_keys.insert(k.clone());
-                key_map.entry(k).or_default().insert(file.lang.clone(), v);
+        let parsed = match resx::parse_resx(&path) {
+            Ok(parsed) => {
+                for warning in &parsed.warnings {
+                    tracing::warn!("{}: {:?}", path.display(), warning);
+                }
+                key_types.extend(parsed.types);
+                for key in parsed.entries.keys() {
+                    key_comments.entry(key.clone()).or_default().insert(file.lang.clone(), parsed.comments.get(key).cloned());
+                }
+                parsed.entries
+            }
+            Err(e) => {
+                tracing::warn!("Skipping {} while loading group: {}", path.display(), e);
+                continue;
             }
+        };
+        // Unless asked to, reloads after our own writes must not reset the baseline
+        if reset_snapshots {
+            snapshots.insert(path.to_string_lossy().to_string(), parsed.clone());
+        } else {
+            snapshots.entry(path.to_string_lossy().to_string()).or_insert_with(|| parsed.clone());
+        }
+        for (k, v) in parsed {
+            all_keys.insert(k.clone());
+            key_sources.entry(k.clone()).or_default().insert(file.path.clone());
+            key_map.entry(k).or_default().insert(file.lang.clone(), v);
         }
     }
 
     let mut rows = Vec::new();
     for key in all_keys {
-        let values = key_map.remove(&key).unwrap_or_default();
-        rows.push(RowData { key, values });
+        let type_attr = key_types.remove(&key);
+        if type_attr.is_some() && !settings.show_binary_entries {
+            continue;
+        }
+        if hidden_keys.iter().any(|re| re.is_match(&key)) {
+            continue;
+        }
+        let mut values = key_map.remove(&key).unwrap_or_default();
+        if type_attr.is_some() {
+            for value in values.values_mut() {
+                if truncate_chars(value, BINARY_PREVIEW_LEN) {
+                    value.push('…');
+                }
+            }
+        }
+        let non_empty: Vec<&String> = values.values().filter(|v| !v.is_empty()).collect();
+        let is_consistent = non_empty.len() > 1 && non_empty.iter().collect::<HashSet<_>>().len() == 1;
+        let source_files = key_sources.remove(&key).unwrap_or_default();
+        let comments = key_comments.remove(&key).unwrap_or_default();
+        rows.push(RowData { key, values, is_consistent, type_attr, source_files, comments });
     }
     
-    rows.sort_by(|a, b| a.key.cmp(&b.key));
+    if !preserve_order {
+        rows.sort_by(|a, b| a.key.cmp(&b.key));
+    }
     Ok(rows)
 }
 
 #[tauri::command]
-fn update_resource(path: &str, key: &str, value: &str) -> Result<(), String> {
-    resx::update_resx_key(Path::new(path), key, value).map_err(|e| e.to_string())
+fn get_value(directory: Option<String>, path: &str, key: &str) -> Result<Option<String>, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    resx::get_value(Path::new(path), key).map_err(|e| e.to_string())
+}
+
+/// Returns the untruncated value of `key`, for entries loaded with `max_value_length`.
+#[tauri::command]
+fn get_full_value(directory: Option<String>, path: &str, key: &str) -> Result<String, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    resx::get_value(Path::new(path), key)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Key '{}' not found", key))
+}
+
+/// Every entry of the file in file order, with its `<comment>`.
+#[tauri::command]
+fn get_entries_with_comments(directory: Option<String>, path: &str) -> Result<Vec<resx::ResxEntry>, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    resx::parse_resx_with_comments(Path::new(path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_value_with_comment(directory: Option<String>, path: &str, key: &str) -> Result<Option<(String, Option<String>)>, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    resx::get_value_with_comment(Path::new(path), key).map_err(|e| e.to_string())
+}
+
+/// Counts the keys of a file, reading no further than `limit` entries if given.
+#[tauri::command]
+fn count_keys(directory: Option<String>, path: &str, limit: Option<usize>) -> Result<usize, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    resx::count_keys_streaming(Path::new(path), limit).map_err(|e| e.to_string())
+}
+
+/// Renames the files of a group so their language codes use canonical casing ("en-us" to "en-US").
+/// Only lists the renames unless `apply` is set. Renames that would replace another file are
+/// skipped and listed in the plan's `skipped`.
+#[tauri::command]
+fn normalize_language_codes(log: State<'_, OperationLog>, directory: &str, group_name: &str, apply: Option<bool>) -> Result<scan::LanguageRenamePlan, String> {
+    let mut plan = scan::plan_language_renames(Path::new(directory), group_name).map_err(|e| e.to_string())?;
+    if apply.unwrap_or(false) {
+        let mut applied = Vec::new();
+        for op in plan.renames {
+            // The target may have been created since the plan was made
+            if Path::new(&op.new_path).exists() && !op.new_path.eq_ignore_ascii_case(&op.old_path) {
+                plan.skipped.push(scan::SkippedRename { old_path: op.old_path, new_path: op.new_path, reason: "A file with the new name already exists".to_string() });
+                continue;
+            }
+            let result = std::fs::rename(&op.old_path, &op.new_path).map_err(|e| format!("{}: {}", op.old_path, e));
+            log.record("normalize_language_codes", &op.old_path, None, result)?;
+            applied.push(op);
+        }
+        plan.renames = applied;
+    }
+    Ok(plan)
+}
+
+#[derive(Serialize)]
+struct CleanReport {
+    /// Keys whose whitespace-only value was (or, in a dry run, would be) set to an empty string.
+    cleared_keys: Vec<String>,
+}
+
+fn clean_file(log: &OperationLog, path: &str, dry_run: bool) -> Result<CleanReport, String> {
+    let result = resx::clear_whitespace_only_values(Path::new(path), dry_run).map_err(|e| format!("{}: {}", path, e));
+    let cleared_keys = if dry_run { result? } else { log.record("clean_whitespace_only_values", path, None, result)? };
+    Ok(CleanReport { cleared_keys })
+}
+
+/// Empties values that are only spaces, tabs or line breaks. With `dry_run` set, nothing is written.
+#[tauri::command]
+fn clean_whitespace_only_values(log: State<'_, OperationLog>, directory: Option<String>, path: &str, dry_run: bool) -> Result<CleanReport, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    clean_file(&log, path, dry_run)
+}
+
+/// `clean_whitespace_only_values` for every file of a group, keyed by the file's path in the group.
+#[tauri::command]
+fn clean_whitespace_only_values_in_group(log: State<'_, OperationLog>, group: ResxGroup, dry_run: bool) -> Result<HashMap<String, CleanReport>, String> {
+    group.files.iter()
+        .map(|file| Ok((file.path.clone(), clean_file(&log, &group.file_path(file).to_string_lossy(), dry_run)?)))
+        .collect()
+}
+
+/// Key, value length and size figures for a file's info panel.
+#[tauri::command]
+fn get_file_statistics(directory: Option<String>, path: &str) -> Result<resx::FileStatistics, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    resx::file_statistics(Path::new(path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_file_encoding(directory: Option<String>, path: &str) -> Result<resx::FileEncoding, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    resx::detect_encoding(Path::new(path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_file_mtime(directory: Option<String>, path: &str) -> Result<u64, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    scan::file_mtime_ms(Path::new(path)).map_err(|e| e.to_string())
+}
+
+/// With `expected_mtime` set (from `get_file_mtime` at load time), the update fails instead of
+/// overwriting changes made to the file in the meantime.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn update_resource(app: AppHandle, log: State<'_, OperationLog>, directory: Option<String>, path: &str, key: &str, value: &str, verify: Option<bool>, expected_mtime: Option<u64>) -> Result<usize, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    if let Some(expected) = expected_mtime {
+        let actual = scan::file_mtime_ms(Path::new(path)).map_err(|e| e.to_string())?;
+        if actual != expected {
+            return Err(format!("{} was modified since it was loaded", path));
+        }
+    }
+    let result = resx::update_resx_key(Path::new(path), key, value, true, verify.unwrap_or(false), &write_options(&app)).map_err(|e| describe_key_error(path, e));
+    log.record("update_resource", path, Some(key), result)
+}
+
+/// Updates `key` in every file of a group whose language has an entry in `values` (lang -> value).
+/// Returns the outcome per file (by its path in the group); files without a value for their language are left out.
+#[tauri::command]
+fn update_resource_in_group(app: AppHandle, log: State<'_, OperationLog>, group: ResxGroup, key: &str, values: HashMap<String, String>) -> HashMap<String, Result<(), String>> {
+    let options = write_options(&app);
+    group.files.iter()
+        .filter_map(|file| {
+            let value = values.get(&file.lang)?;
+            let path = group.file_path(file).to_string_lossy().to_string();
+            let result = resx::update_resx_key(Path::new(&path), key, value, true, false, &options)
+                .map(|_| ())
+                .map_err(|e| describe_key_error(&path, e));
+            let result = log.record("update_resource_in_group", &path, Some(key), result);
+            Some((file.path.clone(), result))
+        })
+        .collect()
+}
+
+fn write_options(app: &AppHandle) -> resx::ResxWriteOptions {
+    settings::load_settings(app).write_options
 }
 
 #[tauri::command]
-fn add_key(path: &str, key: &str) -> Result<(), String> {
+fn add_key(app: AppHandle, log: State<'_, OperationLog>, directory: Option<String>, path: &str, key: &str, if_exists: Option<resx::IfExistsPolicy>, verify: Option<bool>) -> Result<(), String> {
+    let path = &resolve_path(directory.as_deref(), path);
     // Adds key with empty value
-    resx::add_resx_key(Path::new(path), key, "").map_err(|e| e.to_string())
+    let if_exists = if_exists.unwrap_or_default();
+    let result = resx::add_resx_key(Path::new(path), key, "", None, if_exists, verify.unwrap_or(false), &write_options(&app)).map(|_| ()).map_err(|e| e.to_string());
+    log.record("add_key", path, Some(key), result)
 }
 
+/// Appends `key` with `value` and, if given, a `<comment>` describing it for translators.
 #[tauri::command]
-fn remove_key(path: &str, key: &str) -> Result<usize, String> {
-    resx::remove_resx_key(Path::new(path), key).map_err(|e| e.to_string())
+fn add_key_with_comment(app: AppHandle, log: State<'_, OperationLog>, directory: Option<String>, path: &str, key: &str, value: &str, comment: Option<String>) -> Result<(), String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::add_resx_key(Path::new(path), key, value, comment.as_deref(), resx::IfExistsPolicy::Error, false, &write_options(&app))
+        .map(|_| ())
+        .map_err(|e| e.to_string());
+    log.record("add_key_with_comment", path, Some(key), result)
+}
+
+/// Sets the `<comment>` of `key`, or removes it if `comment` is `None`.
+#[tauri::command]
+fn update_comment(log: State<'_, OperationLog>, directory: Option<String>, path: &str, key: &str, comment: Option<String>) -> Result<(), String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::update_resx_comment(Path::new(path), key, comment.as_deref()).map_err(|e| describe_key_error(path, e));
+    log.record("update_comment", path, Some(key), result)
+}
+
+/// Returns how many entries were removed: normally 1, 0 if the file didn't have `key`.
+#[tauri::command]
+fn remove_key(log: State<'_, OperationLog>, directory: Option<String>, path: &str, key: &str, verify: Option<bool>) -> Result<usize, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::remove_resx_key(Path::new(path), key, false, verify.unwrap_or(false)).map_err(|e| e.to_string());
+    log.record("remove_key", path, Some(key), result)
+}
+
+/// Returns the file content as it would be after removing `key`, so the UI can show a diff.
+#[tauri::command]
+fn preview_remove_key(directory: Option<String>, path: &str, key: &str) -> Result<String, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    resx::preview_remove_resx_key(Path::new(path), key).map_err(|e| e.to_string())
 }
 
 #[derive(Deserialize)]
 struct BatchInsertItem {
     key: String,
     value: String,
+    /// Position in the file after all insertions, not in the original file.
     index: usize,
 }
 
 #[tauri::command]
-fn insert_key(path: &str, key: &str, value: &str, index: usize) -> Result<(), String> {
-    resx::insert_resx_key(Path::new(path), key, value, index).map_err(|e| e.to_string())
+#[allow(clippy::too_many_arguments)]
+fn insert_key(app: AppHandle, log: State<'_, OperationLog>, directory: Option<String>, path: &str, key: &str, value: &str, index: usize, verify: Option<bool>) -> Result<(), String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::insert_resx_key(Path::new(path), key, value, index, verify.unwrap_or(false), &write_options(&app)).map(|_| ()).map_err(|e| e.to_string());
+    log.record("insert_key", path, Some(key), result)
 }
 
 #[tauri::command]
-fn batch_insert_keys(path: &str, items: Vec<BatchInsertItem>) -> Result<(), String> {
+fn batch_insert_keys(app: AppHandle, log: State<'_, OperationLog>, directory: Option<String>, path: &str, items: Vec<BatchInsertItem>, verify: Option<bool>) -> Result<(), String> {
+    let path = &resolve_path(directory.as_deref(), path);
     let items: Vec<resx::ResxInsert> = items.into_iter().map(|i| resx::ResxInsert {
         key: i.key,
         value: i.value,
+        comment: None,
         index: i.index,
     }).collect();
-    resx::insert_resx_keys(Path::new(path), items).map_err(|e| e.to_string())
+    let result = resx::count_keys_streaming(Path::new(path), None)
+        .and_then(|key_count| resx::check_insert_indices(&items, key_count))
+        .and_then(|_| resx::insert_resx_keys(Path::new(path), items, &write_options(&app), verify.unwrap_or(false)))
+        .map(|_| ())
+        .map_err(|e| e.to_string());
+    log.record("batch_insert_keys", path, None, result)
 }
 
 #[tauri::command]
-fn batch_remove_keys(path: &str, keys: Vec<String>) -> Result<HashMap<String, usize>, String> {
+fn batch_remove_keys(log: State<'_, OperationLog>, directory: Option<String>, path: &str, keys: Vec<String>, verify: Option<bool>) -> Result<HashMap<String, usize>, String> {
+    let path = &resolve_path(directory.as_deref(), path);
     let key_set: HashSet<String> = keys.into_iter().collect();
-    resx::remove_resx_keys(Path::new(path), &key_set).map_err(|e| e.to_string())
+    let result = resx::remove_resx_keys(Path::new(path), &key_set, verify.unwrap_or(false)).map_err(|e| e.to_string());
+    log.record("batch_remove_keys", path, None, result)
+}
+
+#[tauri::command]
+fn batch_update_resources(app: AppHandle, log: State<'_, OperationLog>, directory: Option<String>, path: &str, updates: HashMap<String, String>, verify: Option<bool>) -> Result<resx::BatchUpdateReport, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::update_resx_keys(Path::new(path), &updates, true, verify.unwrap_or(false), &write_options(&app)).map_err(|e| e.to_string());
+    log.record("batch_update_resources", path, None, result)
 }
 
+/// Returns the `max_suggestions` keys of the file most similar to `query`, for typos.
 #[tauri::command]
-fn batch_update_resources(path: &str, updates: HashMap<String, String>) -> Result<(), String> {
-    resx::update_resx_keys(Path::new(path), &updates).map_err(|e| e.to_string())
+fn suggest_similar_keys(directory: Option<String>, path: &str, query: &str, max_suggestions: usize) -> Result<Vec<analysis::KeySuggestion>, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let keys = resx::parse_resx_keys(Path::new(path)).map_err(|e| e.to_string())?;
+    Ok(analysis::suggest_similar_keys(&keys, query, max_suggestions))
 }
 
+/// Turns an error into a message, pointing out the closest existing key if it's about a missing key.
+fn describe_key_error(path: &str, error: anyhow::Error) -> String {
+    let Some(resx::ResxError::KeyNotFound(key)) = error.downcast_ref::<resx::ResxError>() else {
+        return error.to_string();
+    };
+    let closest = resx::parse_resx_keys(Path::new(path)).ok()
+        .and_then(|keys| analysis::suggest_similar_keys(&keys, key, 1).into_iter().next());
+    match closest {
+        Some(s) if s.similarity >= 0.8 => format!("{}. Did you mean '{}' ({:.0}% similar)?", error, s.key, s.similarity * 100.0),
+        _ => error.to_string(),
+    }
+}
+
+/// Moves `key` to position `new_index` among the file's keys and returns its previous index.
 #[tauri::command]
-fn rename_key(path: &str, old_key: &str, new_key: &str) -> Result<(), String> {
-    resx::rename_resx_key(Path::new(path), old_key, new_key).map_err(|e| e.to_string())
+fn move_key(log: State<'_, OperationLog>, directory: Option<String>, path: &str, key: &str, new_index: usize) -> Result<usize, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::move_resx_key(Path::new(path), key, new_index).map_err(|e| describe_key_error(path, e));
+    log.record("move_key", path, Some(key), result)
 }
 
 #[tauri::command]
-fn watch_group(app: AppHandle, directory: String) -> Result<(), String> {
-    let state = app.state::<WatcherState>();
-    let mut watcher_guard = state.watcher.lock().map_err(|e| e.to_string())?;
+fn rename_key(log: State<'_, OperationLog>, directory: Option<String>, path: &str, old_key: &str, new_key: &str, verify: Option<bool>) -> Result<(), String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::rename_resx_key(Path::new(path), old_key, new_key, verify.unwrap_or(false)).map(|_| ()).map_err(|e| describe_key_error(path, e));
+    log.record("rename_key", path, Some(old_key), result)
+}
+
+#[derive(Serialize, Default)]
+struct GroupRenameReport {
+    renamed_files: Vec<String>,
+    not_found_in: Vec<String>,
+    errors: Vec<(String, String)>,
+}
+
+/// Renames a key in every file of the group `group_name` located directly in `directory`.
+/// Nothing is written if `new_key` already exists in any of the files.
+#[tauri::command]
+fn rename_key_across_group(log: State<'_, OperationLog>, directory: &str, group_name: &str, old_key: &str, new_key: &str) -> Result<GroupRenameReport, String> {
+    let root = scan::resolve_root(directory)?;
+    let options = ScanOptions { max_depth: Some(1), ..Default::default() };
+    let groups = scan::scan_groups(&root, &options, |_, _| {}).groups;
+    let group = groups.into_iter()
+        .find(|g| g.name == group_name)
+        .ok_or_else(|| format!("Group '{}' not found in '{}'", group_name, directory))?;
+
+    let mut files_with_key = Vec::new();
+    let mut report = GroupRenameReport::default();
+    for file in &group.files {
+        let path = group.file_path(file).to_string_lossy().to_string();
+        let keys = resx::parse_resx_keys(Path::new(&path)).map_err(|e| format!("{}: {}", path, e))?;
+        if keys.iter().any(|k| k == new_key) {
+            return Err(format!("Key '{}' already exists in {}", new_key, path));
+        }
+        if keys.iter().any(|k| k == old_key) {
+            files_with_key.push(path);
+        } else {
+            report.not_found_in.push(path);
+        }
+    }
+
+    for path in files_with_key {
+        let result = resx::rename_resx_key(Path::new(&path), old_key, new_key, false).map_err(|e| e.to_string());
+        match log.record("rename_key_across_group", &path, Some(old_key), result) {
+            Ok(_) => report.renamed_files.push(path),
+            Err(e) => report.errors.push((path, e)),
+        }
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+fn get_change_summary(snapshots: State<'_, SnapshotState>, directory: Option<String>, path: &str) -> Result<Vec<resx::ResxChange>, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let snapshots = snapshots.snapshots.lock().map_err(|e| e.to_string())?;
+    let Some(before) = snapshots.get(path) else {
+        return Ok(Vec::new());
+    };
+    let after = resx::parse_resx(Path::new(path)).map_err(|e| e.to_string())?.entries;
+    Ok(resx::diff_entries(before, &after))
+}
+
+/// Applies a list of changes (e.g. from undo/redo or a merge) in a single write.
+#[tauri::command]
+fn apply_diff(app: AppHandle, log: State<'_, OperationLog>, directory: Option<String>, path: &str, changes: Vec<resx::ResxChange>, verify: Option<bool>) -> Result<resx::ApplyDiffReport, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::apply_diff(Path::new(path), changes, &write_options(&app), verify.unwrap_or(false)).map_err(|e| e.to_string());
+    log.record("apply_diff", path, None, result)
+}
+
+#[derive(Deserialize)]
+struct RowDataEntry {
+    key: String,
+    value: String,
+}
+
+/// Diffs the file currently on disk against the editor's in-memory state.
+/// Changes are expressed as going from the disk version to the in-memory version.
+#[tauri::command]
+fn diff_with_disk(directory: Option<String>, path: &str, in_memory_state: Vec<RowDataEntry>) -> Result<Vec<resx::ResxChange>, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let on_disk = resx::parse_resx(Path::new(path)).map_err(|e| e.to_string())?.entries;
+    let in_memory: IndexMap<String, String> = in_memory_state.into_iter().map(|e| (e.key, e.value)).collect();
+    Ok(resx::diff_entries(&on_disk, &in_memory))
+}
+
+/// File events that have not been emitted yet, combined into one kind per path.
+#[derive(Default)]
+struct PendingEvents {
+    /// Time of the most recent event. While it is `Some`, a timer thread is already waiting to emit.
+    last_at: Option<Instant>,
+    events: HashMap<String, FileEventKind>,
+}
+
+/// Saving a file often produces several events in a row (e.g. modify + rename),
+/// so `resx-file-event`s are only emitted once no new event has arrived for `delay`,
+/// and then only one per file, as `FileEventKind::then` combines them.
+fn schedule_debounced_emit(app: &AppHandle, pending: &Arc<Mutex<PendingEvents>>, events: Vec<ResxFileEvent>, delay: Duration) {
+    let Ok(mut guard) = pending.lock() else { return };
+    for event in events {
+        let kind = match guard.events.remove(&event.path) {
+            Some(earlier) => earlier.then(event.kind),
+            None => Some(event.kind),
+        };
+        if let Some(kind) = kind {
+            guard.events.insert(event.path, kind);
+        }
+    }
+    let timer_running = guard.last_at.is_some();
+    guard.last_at = Some(Instant::now());
+    if timer_running {
+        return;
+    }
+
+    let app = app.clone();
+    let pending = Arc::clone(pending);
+    std::thread::spawn(move || {
+        let mut wait = delay;
+        let events = loop {
+            std::thread::sleep(wait);
+            let Ok(mut guard) = pending.lock() else { return };
+            match guard.last_at {
+                Some(at) if at.elapsed() < delay => wait = delay - at.elapsed(),
+                _ => {
+                    guard.last_at = None;
+                    break std::mem::take(&mut guard.events);
+                }
+            }
+        };
+        for (path, kind) in events {
+            let _ = app.emit("resx-file-event", ResxFileEvent { path, kind });
+        }
+    });
+}
+
+#[derive(Deserialize)]
+enum KeyListFormat {
+    Json,
+    NewlineSeparated,
+    Csv,
+}
+
+/// Exports the keys of `lang`'s file, or the union of keys across all files when `lang` is `None`.
+/// Keys keep the order they are first seen in (files are scanned in the given order).
+#[tauri::command]
+fn export_key_list(group: ResxGroup, lang: Option<String>, format: KeyListFormat) -> Result<String, String> {
+    let mut seen = HashSet::new();
+    let mut keys = Vec::new();
 
+    for file in group.files.iter().filter(|f| lang.as_ref().is_none_or(|l| &f.lang == l)) {
+        for key in resx::parse_resx_keys(&group.file_path(file)).map_err(|e| e.to_string())? {
+            if seen.insert(key.clone()) {
+                keys.push(key);
+            }
+        }
+    }
+
+    match format {
+        KeyListFormat::Json => serde_json::to_string_pretty(&keys).map_err(|e| e.to_string()),
+        KeyListFormat::NewlineSeparated => Ok(keys.join("\n")),
+        KeyListFormat::Csv => {
+            let mut out = String::from("key\n");
+            for key in &keys {
+                out.push_str(&csv_field(key));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Writes a TypeScript file with a constant object of the keys of `lang`'s file, or of all files when
+/// `lang` is `None` (values are then taken from the first file that has the key). The object is named
+/// after the group and a union type of the keys is included unless `include_key_type` is false.
+#[tauri::command]
+fn export_group_as_ts_consts(log: State<'_, OperationLog>, group: ResxGroup, lang: Option<String>, output_path: &str, mode: analysis::TsExportMode, include_key_type: Option<bool>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for file in group.files.iter().filter(|f| lang.as_ref().is_none_or(|l| &f.lang == l)) {
+        let path = group.file_path(file);
+        let values = resx::parse_resx(&path).map_err(|e| format!("{}: {}", path.display(), e))?.entries;
+        for (key, value) in values {
+            if seen.insert(key.clone()) {
+                entries.push((key, value));
+            }
+        }
+    }
+
+    let const_name: String = group.name.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '_').collect();
+    let const_name = if const_name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') { const_name } else { "Resources".to_string() };
+
+    let module = analysis::ts_consts_module(&const_name, &entries, mode, include_key_type.unwrap_or(true)).map_err(|e| e.to_string())?;
+    let result = resx::write_atomically(Path::new(output_path), module.as_bytes()).map_err(|e| e.to_string());
+    log.record("export_group_as_ts_consts", output_path, None, result)
+}
+
+#[derive(Serialize, Default)]
+struct ImportKeyListReport {
+    added: Vec<String>,
+    skipped_existing: Vec<String>,
+    errors: Vec<String>,
+}
+
+/// Appends every key from `keys` that the file doesn't have yet, all with `default_value`.
+/// Existing keys are left untouched.
+#[tauri::command]
+fn import_key_list(app: AppHandle, log: State<'_, OperationLog>, directory: Option<String>, path: &str, keys: Vec<String>, default_value: Option<String>) -> Result<ImportKeyListReport, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let mut existing: HashSet<String> = resx::parse_resx_keys(Path::new(path))
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .collect();
+    let value = default_value.unwrap_or_default();
+
+    let mut report = ImportKeyListReport::default();
+    let mut items = Vec::new();
+    for key in keys {
+        if key.trim().is_empty() {
+            report.errors.push("Skipped an empty key".to_string());
+        } else if existing.contains(&key) {
+            report.skipped_existing.push(key);
+        } else {
+            existing.insert(key.clone());
+            items.push(resx::ResxInsert { key: key.clone(), value: value.clone(), comment: None, index: usize::MAX });
+            report.added.push(key);
+        }
+    }
+
+    if !items.is_empty() {
+        let result = resx::insert_resx_keys(Path::new(path), items, &write_options(&app), false).map_err(|e| e.to_string());
+        log.record("import_key_list", path, None, result)?;
+    }
+    Ok(report)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[tauri::command]
+fn compare_file_with_default(default_file: &str, translated_file: &str) -> Result<Vec<analysis::ComparisonEntry>, String> {
+    analysis::compare_file_with_default(Path::new(default_file), Path::new(translated_file)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn find_keys_by_value(directory: Option<String>, path: &str, value: &str, case_sensitive: bool) -> Result<Vec<String>, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let entries = resx::parse_resx(Path::new(path)).map_err(|e| e.to_string())?.entries;
+    Ok(analysis::find_keys_by_value(&entries, value, case_sensitive))
+}
+
+/// Like `find_keys_by_value` for every file of a group (or only the one of `lang`),
+/// keyed by the file's path in the group. Files without a match are left out.
+#[tauri::command]
+fn find_keys_by_value_in_group(group: ResxGroup, value: &str, lang: Option<String>, case_sensitive: bool) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut result = HashMap::new();
+    for file in group.files.iter().filter(|f| lang.as_ref().is_none_or(|l| &f.lang == l)) {
+        let path = group.file_path(file);
+        let entries = resx::parse_resx(&path).map_err(|e| format!("{}: {}", path.display(), e))?.entries;
+        let keys = analysis::find_keys_by_value(&entries, value, case_sensitive);
+        if !keys.is_empty() {
+            result.insert(file.path.clone(), keys);
+        }
+    }
+    Ok(result)
+}
+
+/// Parsed entries of a group's default language file and of each translation (with its language).
+struct GroupEntries {
+    default: IndexMap<String, String>,
+    translations: Vec<(String, IndexMap<String, String>)>,
+}
+
+fn parse_default_and_translations(group: &ResxGroup) -> Result<GroupEntries, String> {
+    let default_file = group.files.iter()
+        .find(|f| f.lang == "default")
+        .ok_or_else(|| "The group has no default language file".to_string())?;
+    let default = parse_group_file(group, default_file)?;
+
+    let mut translations = Vec::new();
+    for file in group.files.iter().filter(|f| f.lang != "default") {
+        translations.push((file.lang.clone(), parse_group_file(group, file)?));
+    }
+    Ok(GroupEntries { default, translations })
+}
+
+/// (lang, entries) of each file of a group.
+type EntriesByLang = Vec<(String, IndexMap<String, String>)>;
+
+fn parse_files_by_lang(group: &ResxGroup) -> Result<EntriesByLang, String> {
+    group.files.iter()
+        .map(|file| Ok((file.lang.clone(), parse_group_file(group, file)?)))
+        .collect()
+}
+
+fn parse_group_file(group: &ResxGroup, file: &ResxFile) -> Result<IndexMap<String, String>, String> {
+    let path = group.file_path(file);
+    Ok(resx::parse_resx(&path).map_err(|e| format!("{}: {}", path.display(), e))?.entries)
+}
+
+/// Summarizes the changes between two versions of a group's files, like `git diff --stat`,
+/// e.g. to estimate the cost of a translation round before sending files out.
+#[tauri::command]
+fn calculate_diff_statistics(before: ResxGroup, after: ResxGroup) -> Result<analysis::DiffStatistics, String> {
+    Ok(analysis::diff_statistics(&parse_files_by_lang(&before)?, &parse_files_by_lang(&after)?))
+}
+
+/// Checks that every translation of a group uses the same HTML tags as the default language.
+#[tauri::command]
+fn check_html_tag_consistency(group: ResxGroup) -> Result<Vec<analysis::HtmlTagError>, String> {
+    let GroupEntries { default, translations } = parse_default_and_translations(&group)?;
+    Ok(translations.iter()
+        .flat_map(|(lang, translated)| analysis::check_html_tags(&default, translated, lang))
+        .collect())
+}
+
+/// Scans `scan_path` and writes translation completeness per group and language to a CSV file.
+#[tauri::command]
+fn export_group_stats_as_csv(log: State<'_, OperationLog>, scan_path: &str, output_path: &str) -> Result<(), String> {
+    let root = scan::resolve_root(scan_path)?;
+    let groups = scan::scan_groups(&root, &ScanOptions::default(), |_, _| {}).groups;
+    let mut stats = Vec::new();
+    for group in &groups {
+        stats.push(analysis::group_stats(&group.name, &group.directory, &parse_files_by_lang(group)?));
+    }
+    let result = analysis::write_group_stats_csv(&stats, Path::new(output_path)).map_err(|e| e.to_string());
+    log.record("export_group_stats_as_csv", output_path, None, result)
+}
+
+#[derive(Serialize, Deserialize)]
+struct MissingTranslationsGroup {
+    name: String,
+    directory: String,
+    missing: Vec<analysis::MissingTranslation>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MissingTranslationsFile {
+    generated_at: String,
+    groups: Vec<MissingTranslationsGroup>,
+}
+
+impl MissingTranslationsFile {
+    fn missing_by_language(&self) -> HashMap<String, usize> {
+        analysis::count_missing_by_language(self.groups.iter().flat_map(|g| &g.missing))
+    }
+}
+
+/// Scans `scan_path` and collects the missing translations of every group that has a default file.
+fn collect_missing_translations(scan_path: &str) -> Result<MissingTranslationsFile, String> {
+    let root = scan::resolve_root(scan_path)?;
+    let groups = scan::scan_groups(&root, &ScanOptions::default(), |_, _| {}).groups;
+
+    let mut file = MissingTranslationsFile {
+        generated_at: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+        groups: Vec::new(),
+    };
+    for group in groups.iter().filter(|g| g.has_default_file) {
+        let GroupEntries { default, translations } = parse_default_and_translations(group)?;
+        let missing = analysis::find_missing_translations(&default, &translations);
+        file.groups.push(MissingTranslationsGroup { name: group.name.clone(), directory: group.directory.clone(), missing });
+    }
+    Ok(file)
+}
+
+#[derive(Serialize, Default)]
+struct MissingReport {
+    total_missing: usize,
+    missing_by_language: HashMap<String, usize>,
+}
+
+/// Scans `scan_path` and writes the missing translations of every group to a JSON file for CI,
+/// returning how many are missing per language. Groups without a default file are skipped.
+#[tauri::command]
+fn export_missing_translations_report(log: State<'_, OperationLog>, scan_path: &str, output_path: &str) -> Result<MissingReport, String> {
+    let file = collect_missing_translations(scan_path)?;
+    let missing_by_language = file.missing_by_language();
+    let report = MissingReport { total_missing: missing_by_language.values().sum(), missing_by_language };
+
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    let result = std::fs::write(output_path, json).map_err(|e| e.to_string());
+    log.record("export_missing_translations_report", output_path, None, result)?;
+    Ok(report)
+}
+
+#[derive(Serialize)]
+struct VelocityReport {
+    /// When the baseline report was generated.
+    period_start: String,
+    period_end: String,
+    /// Net number of translations completed per language since the baseline; negative if more are missing now.
+    keys_translated: HashMap<String, i64>,
+}
+
+/// Compares the missing translations under `scan_path` with a report previously written by
+/// `export_missing_translations_report`, to see how translation work has progressed since.
+#[tauri::command]
+fn get_translation_velocity(scan_path: &str, baseline_report_path: &str) -> Result<VelocityReport, String> {
+    let baseline_json = std::fs::read_to_string(baseline_report_path)
+        .map_err(|e| format!("Cannot read baseline report '{}': {}", baseline_report_path, e))?;
+    let baseline: MissingTranslationsFile = serde_json::from_str(&baseline_json)
+        .map_err(|e| format!("Invalid baseline report '{}': {}", baseline_report_path, e))?;
+    let current = collect_missing_translations(scan_path)?;
+    Ok(VelocityReport {
+        keys_translated: analysis::translation_velocity(&baseline.missing_by_language(), &current.missing_by_language()),
+        period_start: baseline.generated_at,
+        period_end: current.generated_at,
+    })
+}
+
+/// Flags translations that are nearly identical to the default value (`threshold` between 0 and 1).
+#[tauri::command]
+fn find_untranslated_by_similarity(group: ResxGroup, threshold: f32) -> Result<Vec<analysis::SimilarityWarning>, String> {
+    let GroupEntries { default, translations } = parse_default_and_translations(&group)?;
+    Ok(translations.iter()
+        .flat_map(|(lang, translated)| analysis::find_similar_to_default(&default, translated, lang, threshold))
+        .collect())
+}
+
+#[derive(Serialize, Default)]
+struct BulkCopyReport {
+    keys_filled: usize,
+    already_had_value: usize,
+    missing_in_default: usize,
+}
+
+/// Fills every empty or missing value in `target_file` with the default language's value,
+/// so translators see the source text inline. Existing translations are never overwritten.
+#[tauri::command]
+fn bulk_copy_untranslated(app: AppHandle, log: State<'_, OperationLog>, default_file: &str, target_file: &str) -> Result<BulkCopyReport, String> {
+    let default = resx::parse_resx(Path::new(default_file)).map_err(|e| e.to_string())?.entries;
+    let target = resx::parse_resx(Path::new(target_file)).map_err(|e| e.to_string())?.entries;
+
+    let mut report = BulkCopyReport {
+        missing_in_default: target.keys().filter(|k| !default.contains_key(*k)).count(),
+        ..Default::default()
+    };
+    let mut updates = HashMap::new();
+    let mut inserts = Vec::new();
+    let mut default_keys: Vec<&String> = default.keys().collect();
+    default_keys.sort();
+
+    for key in default_keys {
+        let default_value = &default[key];
+        match target.get(key) {
+            Some(value) if !value.is_empty() => report.already_had_value += 1,
+            _ if default_value.is_empty() => {}
+            Some(_) => {
+                updates.insert(key.clone(), default_value.clone());
+                report.keys_filled += 1;
+            }
+            None => {
+                inserts.push(resx::ResxInsert { key: key.clone(), value: default_value.clone(), comment: None, index: usize::MAX });
+                report.keys_filled += 1;
+            }
+        }
+    }
+
+    if !updates.is_empty() {
+        let result = resx::update_resx_keys(Path::new(target_file), &updates, true, false, &write_options(&app)).map_err(|e| e.to_string());
+        log.record("bulk_copy_untranslated", target_file, None, result)?;
+    }
+    if !inserts.is_empty() {
+        let result = resx::insert_resx_keys(Path::new(target_file), inserts, &write_options(&app), false).map_err(|e| e.to_string());
+        log.record("bulk_copy_untranslated", target_file, None, result)?;
+    }
+    Ok(report)
+}
+
+/// Sets `key` in `translated_file` back to the default language's value, adding it if needed.
+/// The single-key version of `bulk_copy_untranslated`, except that it overwrites the translation.
+#[tauri::command]
+fn reset_value_to_default(app: AppHandle, log: State<'_, OperationLog>, default_file: &str, translated_file: &str, key: &str) -> Result<(), String> {
+    let result = resx::reset_keys_to_default(Path::new(default_file), Path::new(translated_file), &[key.to_string()], &write_options(&app))
+        .map_err(|e| describe_key_error(default_file, e));
+    log.record("reset_value_to_default", translated_file, Some(key), result)
+}
+
+/// `reset_value_to_default` for several keys, with the files picked from a group by language.
+#[tauri::command]
+fn reset_keys_to_default(app: AppHandle, log: State<'_, OperationLog>, group: ResxGroup, default_lang: &str, target_lang: &str, keys: Vec<String>) -> Result<(), String> {
+    let file_for = |lang: &str| group.files.iter()
+        .find(|f| f.lang == lang)
+        .map(|f| group.file_path(f).to_string_lossy().to_string())
+        .ok_or_else(|| format!("The group has no '{}' file", lang));
+    let (default_file, translated_file) = (file_for(default_lang)?, file_for(target_lang)?);
+    let result = resx::reset_keys_to_default(Path::new(&default_file), Path::new(&translated_file), &keys, &write_options(&app))
+        .map_err(|e| describe_key_error(&default_file, e));
+    log.record("reset_keys_to_default", &translated_file, None, result)
+}
+
+#[tauri::command]
+fn split_resx_by_prefix(app: AppHandle, log: State<'_, OperationLog>, path: &str, output_dir: &str, prefix_separator: char) -> Result<HashMap<String, String>, String> {
+    let result = resx::split_resx_by_prefix(Path::new(path), Path::new(output_dir), prefix_separator, &write_options(&app)).map_err(|e| e.to_string());
+    let outputs = log.record("split_resx_by_prefix", path, None, result)?;
+    Ok(outputs.into_iter().map(|(prefix, path)| (prefix, path.to_string_lossy().to_string())).collect())
+}
+
+/// Copies `keys` from `source_path` into `target_path`, which is created if needed, and with
+/// `remove_from_source` set moves them instead.
+#[tauri::command]
+fn copy_keys_to_new_file(app: AppHandle, log: State<'_, OperationLog>, source_path: &str, target_path: &str, keys: Vec<String>, remove_from_source: bool) -> Result<resx::CopyKeysReport, String> {
+    let result = resx::copy_keys_to_file(Path::new(source_path), Path::new(target_path), &keys, remove_from_source, &write_options(&app)).map_err(|e| e.to_string());
+    log.record("copy_keys_to_new_file", source_path, None, result)
+}
+
+/// Switches hierarchical keys to another separator, e.g. from "Button/Save" to "Button.Save".
+/// Keys whose new name is already taken are reported and left alone.
+#[tauri::command]
+fn convert_keys_to_flat(log: State<'_, OperationLog>, directory: Option<String>, path: &str, separator: char, target_separator: char) -> Result<resx::ConvertReport, String> {
+    let path = &resolve_path(directory.as_deref(), path);
+    let result = resx::convert_key_separators(Path::new(path), separator, target_separator).map_err(|e| e.to_string());
+    log.record("convert_keys_to_flat", path, None, result)
+}
+
+#[derive(Serialize)]
+struct ScaffoldReport {
+    created_files: Vec<String>,
+    /// The new project directory.
+    directory: String,
+}
+
+/// Creates a new project `target_directory/project_name` with a default resx file and one per language.
+/// With `add_to_settings` set, the project is added to the saved groups.
+#[tauri::command]
+fn scaffold_new_project(app: AppHandle, log: State<'_, OperationLog>, target_directory: &str, project_name: &str, languages: Vec<String>, add_to_settings: Option<bool>) -> Result<ScaffoldReport, String> {
+    let result = resx::scaffold_project(Path::new(target_directory), project_name, &languages, &write_options(&app)).map_err(|e| e.to_string());
+    let created = log.record("scaffold_new_project", target_directory, None, result)?;
+    let directory = Path::new(target_directory).join(project_name).to_string_lossy().to_string();
+
+    if add_to_settings.unwrap_or(false) {
+        let mut settings = settings::load_settings(&app);
+        let group = SavedGroup { name: "Resources".to_string(), directory: directory.clone() };
+        if !settings.saved_groups.contains(&group) {
+            settings.saved_groups.push(group);
+            settings::save_settings(&app, &settings)?;
+        }
+    }
+
+    Ok(ScaffoldReport {
+        created_files: created.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+        directory,
+    })
+}
+
+#[tauri::command]
+fn merge_resx_files_by_prefix(app: AppHandle, log: State<'_, OperationLog>, input_dir: &str, pattern: &str, output_path: &str) -> Result<usize, String> {
+    let result = resx::merge_resx_files(Path::new(input_dir), pattern, Path::new(output_path), &write_options(&app)).map_err(|e| e.to_string());
+    log.record("merge_resx_files_by_prefix", output_path, None, result)
+}
+
+/// Rebuilds a missing default file from one of the group's translations.
+#[tauri::command]
+fn generate_default_from_translations(log: State<'_, OperationLog>, source_lang_file: &str, output_path: &str) -> Result<(), String> {
+    let result = resx::generate_default_from_translation(Path::new(source_lang_file), Path::new(output_path)).map_err(|e| e.to_string());
+    log.record("generate_default_from_translations", output_path, None, result)
+}
+
+/// Promotes a translation to the neutral default file of its group. The source is kept.
+#[tauri::command]
+fn convert_to_neutral(log: State<'_, OperationLog>, source_path: &str, target_path: &str) -> Result<(), String> {
+    let result = resx::convert_to_neutral(Path::new(source_path), Path::new(target_path)).map_err(|e| e.to_string());
+    log.record("convert_to_neutral", target_path, None, result)
+}
+
+/// The last `count` operations that changed files, oldest first.
+#[tauri::command]
+fn get_recent_operations(log: State<'_, OperationLog>, count: usize) -> Vec<oplog::OperationEntry> {
+    log.recent(count)
+}
+
+#[tauri::command]
+fn clear_operation_log(log: State<'_, OperationLog>) {
+    log.clear();
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+enum FileEventKind {
+    Modified,
+    Created,
+    Deleted,
+    Unknown,
+}
+
+impl FileEventKind {
+    /// What a file went through overall if `self` happened to it and then `next`, or `None`
+    /// if it ends up as it was, i.e. it was created and deleted again.
+    fn then(self, next: FileEventKind) -> Option<FileEventKind> {
+        use FileEventKind::*;
+        match (self, next) {
+            (Created, Deleted) => None,
+            (Created, _) => Some(Created),
+            (Deleted, Created) => Some(Modified),
+            (Deleted, Modified | Unknown) => Some(Deleted),
+            (_, Unknown) => Some(self),
+            (_, next) => Some(next),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct ResxFileEvent {
+    path: String,
+    kind: FileEventKind,
+}
+
+/// Splits a watcher event into one `ResxFileEvent` per `.resx` path. A rename counts as
+/// deleting the old path and creating the new one. Paths are made relative to the watched
+/// `directory`, like the paths of a group's files.
+///
+/// `known` holds the `.resx` files that exist, and is kept up to date. A file appearing at a
+/// path that is already known, e.g. because an atomic write renamed its temporary file over it,
+/// is reported as modified rather than created.
+fn resx_file_events(event: &notify::Event, directory: &Path, known: &mut HashSet<PathBuf>) -> Vec<ResxFileEvent> {
+    use notify::event::{EventKind, ModifyKind, RenameMode};
+    // Opening, reading or closing a file doesn't change it, and loading a group does all three
+    if matches!(event.kind, EventKind::Access(_)) {
+        return Vec::new();
+    }
+    let is_atomic_write = matches!(event.kind, EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+        && event.paths.len() == 2
+        && resx::atomic_write_tmp_path(&event.paths[1]).as_ref() == Some(&event.paths[0]);
+    event.paths.iter().enumerate()
+        .filter(|(_, p)| p.extension().and_then(|s| s.to_str()) == Some("resx"))
+        .map(|(i, p)| {
+            let kind = match event.kind {
+                EventKind::Remove(_)
+                | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => FileEventKind::Deleted,
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if i == 0 => FileEventKind::Deleted,
+                EventKind::Create(_)
+                | EventKind::Modify(ModifyKind::Name(RenameMode::To))
+                | EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                    if known.insert(p.clone()) && !is_atomic_write { FileEventKind::Created } else { FileEventKind::Modified }
+                }
+                EventKind::Modify(_) => FileEventKind::Modified,
+                _ => FileEventKind::Unknown,
+            };
+            if kind == FileEventKind::Deleted {
+                known.remove(p);
+            }
+            ResxFileEvent { path: p.strip_prefix(directory).unwrap_or(p).to_string_lossy().to_string(), kind }
+        })
+        .collect()
+}
+
+/// Watches `directory` for changes to `.resx` files, including its subdirectories if `recursive` is set.
+/// On Windows, recursive watching of deep directory trees can produce a flood of events
+/// (and may overflow the change buffer), so only enable it where the files are actually nested.
+#[tauri::command]
+fn watch_group(app: AppHandle, directory: String, recursive: Option<bool>) -> Result<(), String> {
+    let mode = if recursive.unwrap_or(false) { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
     let app_handle = app.clone();
-    let mut watcher = RecommendedWatcher::new(move |res: Result<notify::Event, notify::Error>| {
-        match res {
-           Ok(event) => {
-               let is_resx = event.paths.iter().any(|p| p.extension().and_then(|s| s.to_str()) == Some("resx"));
-               if is_resx {
-                   let _ = app_handle.emit("resx-changed", ());
-               }
-           },
-           Err(e) => println!("watch error: {:?}", e),
-        }
-    }, Config::default()).map_err(|e| e.to_string())?;
-
-    watcher.watch(Path::new(&directory), RecursiveMode::NonRecursive).map_err(|e| e.to_string())?;
-    
+    let debounce = Duration::from_millis(settings::load_settings(&app).watch_debounce_ms);
+    let pending: Arc<Mutex<PendingEvents>> = Arc::default();
+    let watched_directory = PathBuf::from(&directory);
+    let mut known: HashSet<PathBuf> = WalkDir::new(&directory)
+        .max_depth(if mode == RecursiveMode::Recursive { usize::MAX } else { 1 })
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("resx"))
+        .collect();
+
+    // The watcher is set up before taking the lock, and a panic in the platform backend is
+    // turned into an error, so a failed setup can't leave the state locked or poisoned.
+    let watcher = std::panic::catch_unwind(AssertUnwindSafe(|| -> Result<ResxFileWatcher, String> {
+        let watcher = RecommendedWatcher::new(move |res: Result<notify::Event, notify::Error>| {
+            match res {
+               Ok(event) => {
+                   let events = resx_file_events(&event, &watched_directory, &mut known);
+                   if !events.is_empty() {
+                       schedule_debounced_emit(&app_handle, &pending, events, debounce);
+                   }
+               },
+               Err(e) => tracing::error!("watch error: {:?}", e),
+            }
+        }, Config::default()).map_err(|e| e.to_string())?;
+
+        let mut watcher = ResxFileWatcher { watcher, watched: Vec::new() };
+        watcher.watch(Path::new(&directory), mode).map_err(|e| e.to_string())?;
+        Ok(watcher)
+    })).map_err(|_| format!("Watching '{}' failed unexpectedly", directory))??;
+
+    let state = app.state::<WatcherState>();
+    // The guarded value is only ever replaced wholesale, so it is still usable after a panic
+    let mut watcher_guard = state.watcher.lock().unwrap_or_else(|poisoned| {
+        tracing::warn!("Watcher state lock was poisoned; recovering");
+        poisoned.into_inner()
+    });
     *watcher_guard = Some(watcher);
     Ok(())
 }
 
+/// Tells the frontend whether the defaults are used because this is the first launch or
+/// because the settings file is corrupt (and will be overwritten on the next save).
+#[tauri::command]
+fn get_app_settings(app: AppHandle) -> settings::LoadSettingsResult {
+    settings::try_load_settings(&app)
+}
+
+/// Returns how many of the oldest saved groups were dropped to stay within `max_saved_groups`.
 #[tauri::command]
-fn get_app_settings(app: AppHandle) -> AppSettings {
-    settings::load_settings(&app)
+fn save_app_settings(app: AppHandle, mut settings: AppSettings) -> Result<usize, String> {
+    let dropped = settings::drop_oldest_groups(&mut settings.saved_groups, settings.max_saved_groups);
+    settings::save_settings(&app, &settings)?;
+    Ok(dropped)
 }
 
 #[tauri::command]
-fn save_app_settings(app: AppHandle, settings: AppSettings) -> Result<(), String> {
-    settings::save_settings(&app, &settings)
+fn trim_saved_groups(app: AppHandle, max: usize) -> Result<usize, String> {
+    settings::trim_saved_groups(&app, max)
+}
+
+#[tauri::command]
+fn reorder_saved_groups(app: AppHandle, new_order: Vec<SavedGroup>) -> Result<(), String> {
+    settings::reorder_saved_groups(&app, new_order)
+}
+
+#[tauri::command]
+fn save_project(log: State<'_, OperationLog>, path: &str, project: project::ResxProjectFile) -> Result<(), String> {
+    let result = project::save_project(Path::new(path), &project);
+    log.record("save_project", path, None, result)
+}
+
+#[tauri::command]
+fn load_project(path: &str) -> Result<project::ResxProjectFile, String> {
+    project::load_project(Path::new(path))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -205,24 +1373,138 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
+            let _ = tracing_subscriber::fmt().try_init();
             app.manage(WatcherState { watcher: Mutex::new(None) });
+            app.manage(SnapshotState { snapshots: Mutex::new(HashMap::new()) });
+            app.manage(OperationLog::new(settings::load_settings(app.handle()).operation_log_size));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             scan_directory,
+            scan_directory_streaming,
+            list_resxfiles_in_directory,
+            scan_directory_incremental,
             load_group,
+            load_group_paged,
+            get_value,
+            get_value_with_comment,
+            get_entries_with_comments,
+            get_full_value,
+            clean_whitespace_only_values,
+            clean_whitespace_only_values_in_group,
+            get_file_statistics,
+            get_file_encoding,
+            normalize_language_codes,
+            get_file_mtime,
+            count_keys,
             update_resource,
+            update_resource_in_group,
             add_key,
+            add_key_with_comment,
+            update_comment,
             insert_key,
             batch_insert_keys,
             remove_key,
+            preview_remove_key,
             batch_remove_keys,
             batch_update_resources,
             rename_key,
+            move_key,
+            suggest_similar_keys,
+            rename_key_across_group,
+            get_change_summary,
+            apply_diff,
+            diff_with_disk,
+            export_key_list,
+            export_group_as_ts_consts,
+            import_key_list,
+            compare_file_with_default,
+            find_keys_by_value,
+            find_keys_by_value_in_group,
+            check_html_tag_consistency,
+            find_untranslated_by_similarity,
+            export_group_stats_as_csv,
+            export_missing_translations_report,
+            get_translation_velocity,
+            calculate_diff_statistics,
+            bulk_copy_untranslated,
+            reset_value_to_default,
+            reset_keys_to_default,
+            split_resx_by_prefix,
+            merge_resx_files_by_prefix,
+            scaffold_new_project,
+            copy_keys_to_new_file,
+            convert_keys_to_flat,
+            auto_detect_project_structure,
+            generate_default_from_translations,
+            convert_to_neutral,
+            get_recent_operations,
+            clear_operation_log,
             watch_group,
             get_app_settings,
-            save_app_settings
+            save_app_settings,
+            trim_saved_groups,
+            reorder_saved_groups,
+            save_project,
+            load_project
         ])
         .run(tauri::generate_context!())
         .expect("error while running EasyResX");
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, AccessMode, CreateKind, DataChange, EventKind, ModifyKind, RemoveKind, RenameMode};
+
+    fn kinds(event: notify::Event, known: &mut HashSet<PathBuf>) -> Vec<(String, FileEventKind)> {
+        resx_file_events(&event, Path::new("/project"), known).into_iter().map(|e| (e.path, e.kind)).collect()
+    }
+
+    #[test]
+    fn test_resx_file_events() {
+        let mut known = HashSet::from([PathBuf::from("/project/Strings.resx")]);
+        let event = |kind| notify::Event::new(kind).add_path(PathBuf::from("/project/New.resx"));
+
+        assert_eq!(kinds(event(EventKind::Create(CreateKind::File)), &mut known), [("New.resx".to_string(), FileEventKind::Created)]);
+        assert!(known.contains(Path::new("/project/New.resx")));
+        assert_eq!(kinds(event(EventKind::Modify(ModifyKind::Data(DataChange::Content))), &mut known), [("New.resx".to_string(), FileEventKind::Modified)]);
+        assert!(kinds(event(EventKind::Access(AccessKind::Close(AccessMode::Write))), &mut known).is_empty());
+        assert!(kinds(event(EventKind::Access(AccessKind::Open(AccessMode::Any))), &mut known).is_empty());
+        assert_eq!(kinds(event(EventKind::Remove(RemoveKind::File)), &mut known), [("New.resx".to_string(), FileEventKind::Deleted)]);
+        assert!(!known.contains(Path::new("/project/New.resx")));
+
+        // Files other than .resx are ignored
+        let other = notify::Event::new(EventKind::Create(CreateKind::File)).add_path(PathBuf::from("/project/notes.txt"));
+        assert!(kinds(other, &mut known).is_empty());
+    }
+
+    #[test]
+    fn test_resx_file_events_for_renames() {
+        let mut known = HashSet::from([PathBuf::from("/project/Strings.resx")]);
+        let rename = |from: &str, to: &str| notify::Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(PathBuf::from(from))
+            .add_path(PathBuf::from(to));
+
+        // An atomic write renames its temporary file over the original
+        assert_eq!(kinds(rename("/project/.Strings.resx.tmp", "/project/Strings.resx"), &mut known), [("Strings.resx".to_string(), FileEventKind::Modified)]);
+        assert_eq!(kinds(rename("/project/Strings.resx", "/project/Other.resx"), &mut known), [
+            ("Strings.resx".to_string(), FileEventKind::Deleted),
+            ("Other.resx".to_string(), FileEventKind::Created),
+        ]);
+        assert_eq!(known, HashSet::from([PathBuf::from("/project/Other.resx")]));
+    }
+
+    #[test]
+    fn test_file_event_kinds_combine() {
+        use FileEventKind::*;
+        assert_eq!(Created.then(Modified), Some(Created));
+        assert_eq!(Created.then(Unknown), Some(Created));
+        assert_eq!(Created.then(Deleted), None);
+        assert_eq!(Deleted.then(Created), Some(Modified));
+        assert_eq!(Deleted.then(Unknown), Some(Deleted));
+        assert_eq!(Modified.then(Deleted), Some(Deleted));
+        assert_eq!(Modified.then(Unknown), Some(Modified));
+        assert_eq!(Unknown.then(Modified), Some(Modified));
+    }
+}
@@ -0,0 +1,105 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Debug, Clone)]
+pub enum OperationResult {
+    Success,
+    Failure(String),
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OperationEntry {
+    pub timestamp_ms: u64,
+    pub command: String,
+    pub path: String,
+    pub key: Option<String>,
+    pub result: OperationResult,
+}
+
+/// The most recent commands that changed files, oldest first, for reproducing reported bugs.
+/// Only the last `capacity` entries are kept.
+pub struct OperationLog {
+    entries: Mutex<VecDeque<OperationEntry>>,
+    capacity: usize,
+}
+
+impl OperationLog {
+    pub fn new(capacity: usize) -> Self {
+        Self { entries: Mutex::new(VecDeque::with_capacity(capacity)), capacity }
+    }
+
+    /// Appends an entry for `result` and hands it back, so a command can end with
+    /// `log.record("name", path, key, result)`.
+    pub fn record<T>(&self, command: &str, path: &str, key: Option<&str>, result: Result<T, String>) -> Result<T, String> {
+        let entry = OperationEntry {
+            timestamp_ms: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+            command: command.to_string(),
+            path: path.to_string(),
+            key: key.map(str::to_string),
+            result: match &result {
+                Ok(_) => OperationResult::Success,
+                Err(e) => OperationResult::Failure(e.clone()),
+            },
+        };
+        // A log entry is not worth failing the command over, so a poisoned lock is recovered
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        if self.capacity > 0 {
+            entries.push_back(entry);
+        }
+        result
+    }
+
+    /// The last `count` entries, oldest first.
+    pub fn recent(&self, count: usize) -> Vec<OperationEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        entries.iter().skip(entries.len().saturating_sub(count)).cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(entries: &[OperationEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.command.as_str()).collect()
+    }
+
+    #[test]
+    fn test_oldest_entries_are_evicted() {
+        let log = OperationLog::new(2);
+        for command in ["first", "second", "third"] {
+            let _ = log.record(command, "a.resx", None, Ok::<_, String>(()));
+        }
+        assert_eq!(commands(&log.recent(10)), ["second", "third"]);
+    }
+
+    #[test]
+    fn test_zero_capacity_keeps_nothing() {
+        let log = OperationLog::new(0);
+        let result = log.record("failed", "a.resx", Some("Key"), Err::<(), _>("error".to_string()));
+        assert_eq!(result, Err("error".to_string()));
+        assert!(log.recent(10).is_empty());
+    }
+
+    #[test]
+    fn test_recent_returns_the_latest_oldest_first() {
+        let log = OperationLog::new(10);
+        for command in ["first", "second", "third"] {
+            let _ = log.record(command, "a.resx", None, Ok::<_, String>(()));
+        }
+        assert_eq!(commands(&log.recent(2)), ["second", "third"]);
+        assert!(log.recent(0).is_empty());
+
+        log.clear();
+        assert!(log.recent(10).is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use crate::scan::ScanOptions;
+
+/// Project files use this extension by convention; it's added when saving to a path without one.
+pub const PROJECT_EXTENSION: &str = "easyresx";
+const PROJECT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScanRoot {
+    pub path: String,
+    #[serde(default)]
+    pub options: ScanOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResxProjectFile {
+    pub name: String,
+    pub version: u32,
+    pub scan_roots: Vec<ScanRoot>,
+    /// Ids of the groups that are open in the project.
+    #[serde(default)]
+    pub active_groups: Vec<String>,
+}
+
+pub fn save_project(path: &Path, project: &ResxProjectFile) -> Result<(), String> {
+    let path = if path.extension().is_none() { path.with_extension(PROJECT_EXTENSION) } else { path.to_path_buf() };
+    let project = ResxProjectFile { version: PROJECT_VERSION, ..project.clone() };
+    let content = serde_json::to_string_pretty(&project).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+pub fn load_project(path: &Path) -> Result<ResxProjectFile, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Cannot read project '{}': {}", path.display(), e))?;
+    let project: ResxProjectFile = serde_json::from_str(&content).map_err(|e| format!("Invalid project file '{}': {}", path.display(), e))?;
+    if project.version > PROJECT_VERSION {
+        return Err(format!("Project '{}' was saved by a newer version (format {})", path.display(), project.version));
+    }
+    Ok(project)
+}
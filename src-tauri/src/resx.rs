@@ -1,11 +1,13 @@
 use anyhow::{Context, Result};
-use quick_xml::events::{BytesText, Event};
+use quick_xml::events::{BytesCData, BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::reader::Reader;
 use quick_xml::writer::Writer;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use indexmap::IndexMap;
 use std::fs;
-use std::io::Cursor;
-use std::path::Path;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
 
 fn minimal_escape(data: &str) -> String {
     data.replace("&", "&amp;")
@@ -13,15 +15,158 @@ fn minimal_escape(data: &str) -> String {
         .replace(">", "&gt;")
 }
 
-pub fn parse_resx(path: &Path) -> Result<HashMap<String, String>> {
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FileEncoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Unknown,
+}
+
+/// Whether `path` starts with a UTF-8 byte order mark. Visual Studio saves .resx files with one,
+/// and sees its removal as a change, so writes keep it.
+pub fn has_bom(path: &Path) -> bool {
+    let mut head = [0; 3];
+    fs::File::open(path).and_then(|mut f| f.read_exact(&mut head)).is_ok() && head == [0xEF, 0xBB, 0xBF]
+}
+
+/// Puts the UTF-8 byte order mark back in front of `result` if the original had one and the
+/// XML writer dropped it.
+fn keep_bom(mut result: Vec<u8>, had_bom: bool) -> Vec<u8> {
+    if had_bom && !result.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        result.splice(0..0, [0xEF, 0xBB, 0xBF]);
+    }
+    result
+}
+
+/// Detects a file's encoding from its byte order mark, falling back to the byte pattern of
+/// the XML declaration and its `encoding` attribute when there is no BOM.
+pub fn detect_encoding(path: &Path) -> Result<FileEncoding> {
+    let mut head = Vec::with_capacity(1024);
+    fs::File::open(path)?.take(1024).read_to_end(&mut head)?;
+
+    let encoding = match head.as_slice() {
+        [0xEF, 0xBB, 0xBF, ..] => FileEncoding::Utf8Bom,
+        [0xFF, 0xFE, ..] => FileEncoding::Utf16Le,
+        [0xFE, 0xFF, ..] => FileEncoding::Utf16Be,
+        [b'<', 0, b'?', 0, ..] => FileEncoding::Utf16Le,
+        [0, b'<', 0, b'?', ..] => FileEncoding::Utf16Be,
+        _ => {
+            let text = String::from_utf8_lossy(&head).to_ascii_lowercase();
+            let declared = text.find("encoding=").and_then(|idx| {
+                let rest = &text[idx + "encoding=".len()..];
+                let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                rest[1..].split(quote).next().map(str::to_string)
+            });
+            match declared.as_deref() {
+                None | Some("utf-8") | Some("utf8") => FileEncoding::Utf8,
+                Some(_) => FileEncoding::Unknown,
+            }
+        }
+    };
+    Ok(encoding)
+}
+
+/// A single difference between two versions of a resx file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ResxChange {
+    Added { key: String, value: String },
+    Removed { key: String, value: String },
+    Modified { key: String, old_value: String, new_value: String },
+    Renamed { old_key: String, new_key: String },
+}
+
+impl ResxChange {
+    /// The key the change applies to; for a rename, the key before renaming.
+    pub fn key(&self) -> &str {
+        match self {
+            ResxChange::Added { key, .. } | ResxChange::Removed { key, .. } | ResxChange::Modified { key, .. } => key,
+            ResxChange::Renamed { old_key, .. } => old_key,
+        }
+    }
+}
+
+/// Compares two key/value maps and returns the changes needed to go from `before` to `after`,
+/// sorted by key so the result is stable.
+pub fn diff_entries(before: &IndexMap<String, String>, after: &IndexMap<String, String>) -> Vec<ResxChange> {
+    let mut changes = Vec::new();
+
+    for (key, old_value) in before {
+        match after.get(key) {
+            None => changes.push(ResxChange::Removed { key: key.clone(), value: old_value.clone() }),
+            Some(new_value) if new_value != old_value => changes.push(ResxChange::Modified {
+                key: key.clone(),
+                old_value: old_value.clone(),
+                new_value: new_value.clone(),
+            }),
+            _ => {}
+        }
+    }
+    for (key, value) in after {
+        if !before.contains_key(key) {
+            changes.push(ResxChange::Added { key: key.clone(), value: value.clone() });
+        }
+    }
+
+    changes.sort_by(|a, b| a.key().cmp(b.key()));
+    changes
+}
+
+/// Something suspicious found while parsing that doesn't prevent reading the rest of the file.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ParseWarning {
+    /// A `<data>` element without a name (or with `name=""`); its value is not included in the entries.
+    EmptyKey { position: u64 },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseResult {
+    /// In file order. A key that occurs more than once keeps the position of its first
+    /// occurrence and the value of its last, like .NET's `ResXResourceReader`.
+    pub entries: IndexMap<String, String>,
+    pub warnings: Vec<ParseWarning>,
+    /// The `type` attribute of entries that have one, i.e. non-string resources such as images.
+    pub types: HashMap<String, String>,
+    /// The `<comment>` of entries that have one.
+    pub comments: HashMap<String, String>,
+}
+
+/// The `name` and `type` attributes of a `<data>` element.
+fn data_attributes(e: &BytesStart) -> Result<(String, Option<String>)> {
+    let mut name = String::new();
+    let mut type_attr = None;
+    for attr in e.attributes() {
+        let attr = attr?;
+        match attr.key.as_ref() {
+            b"name" => name = attr.unescape_value()?.to_string(),
+            b"type" => type_attr = Some(attr.unescape_value()?.to_string()),
+            _ => {}
+        }
+    }
+    Ok((name, type_attr))
+}
+
+/// The start tag for expanding a self-closing element `e`, without the whitespace before its `/>`.
+fn expanded_start(e: &BytesStart) -> BytesStart<'static> {
+    BytesStart::from_content(String::from_utf8_lossy(e).trim_end().to_string(), e.name().as_ref().len())
+}
+
+pub fn parse_resx(path: &Path) -> Result<ParseResult> {
     let mut reader = Reader::from_file(path).context("Failed to open file")?;
     reader.config_mut().trim_text(false);
 
     let mut buf = Vec::new();
-    let mut entries = HashMap::new();
+    let mut result = ParseResult::default();
+    let mut data_position = 0;
     let mut current_key = String::new();
     let mut current_value = String::new();
+    let mut current_type: Option<String> = None;
+    let mut current_comment: Option<String> = None;
     let mut in_value = false;
+    let mut in_comment = false;
     let mut processing_data = false;
 
     loop {
@@ -29,35 +174,71 @@ pub fn parse_resx(path: &Path) -> Result<HashMap<String, String>> {
             Ok(Event::Start(ref e)) => {
                 if e.name().as_ref() == b"data" {
                     processing_data = true;
-                    current_key.clear();
+                    data_position = reader.buffer_position();
                     current_value.clear();
-                    for attr in e.attributes() {
-                        let attr = attr?;
-                        if attr.key.as_ref() == b"name" {
-                            current_key = attr.unescape_value()?.to_string();
-                        }
-                    }
-                } else if e.name().as_ref() == b"value" {
-                    if processing_data {
-                        in_value = true;
-                        current_value.clear();
-                    }
+                    current_comment = None;
+                    (current_key, current_type) = data_attributes(e)?;
+                } else if e.name().as_ref() == b"value" && processing_data {
+                    in_value = true;
+                    current_value.clear();
+                } else if e.name().as_ref() == b"comment" && processing_data {
+                    in_comment = true;
+                    current_comment = Some(String::new());
+                } else if e.name().as_ref() == b"assembly" {
+                    // Only valid next to <data>; in a malformed file, don't let it extend a value
+                    processing_data = false;
+                    in_value = false;
+                    in_comment = false;
                 }
             }
-            Ok(Event::Text(e)) => {
-                if in_value {
-                    current_value.push_str(&e.unescape()?);
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"assembly" => {
+                processing_data = false;
+                in_value = false;
+                in_comment = false;
+            }
+            // <data name="X"/> is an entry with an empty value
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                let (key, type_attr) = data_attributes(e)?;
+                if key.is_empty() {
+                    result.warnings.push(ParseWarning::EmptyKey { position: reader.buffer_position() });
+                } else {
+                    if let Some(type_attr) = type_attr {
+                        result.types.insert(key.clone(), type_attr);
+                    }
+                    result.entries.insert(key, String::new());
                 }
             }
+            Ok(Event::Text(e)) if in_value => {
+                current_value.push_str(&e.unescape()?);
+            }
+            Ok(Event::CData(e)) if in_value => {
+                current_value.push_str(std::str::from_utf8(&e)?);
+            }
+            Ok(Event::Text(e)) if in_comment => {
+                current_comment.get_or_insert_with(String::new).push_str(&e.unescape()?);
+            }
+            Ok(Event::CData(e)) if in_comment => {
+                current_comment.get_or_insert_with(String::new).push_str(std::str::from_utf8(&e)?);
+            }
             Ok(Event::End(ref e)) => {
                 if e.name().as_ref() == b"data" {
-                    if !current_key.is_empty() {
-                        entries.insert(current_key.clone(), current_value.clone());
+                    if current_key.is_empty() {
+                        result.warnings.push(ParseWarning::EmptyKey { position: data_position });
+                    } else {
+                        result.entries.insert(current_key.clone(), current_value.clone());
+                        if let Some(type_attr) = current_type.take() {
+                            result.types.insert(current_key.clone(), type_attr);
+                        }
+                        if let Some(comment) = current_comment.take() {
+                            result.comments.insert(current_key.clone(), comment);
+                        }
                     }
                     processing_data = false;
                     current_key.clear();
                 } else if e.name().as_ref() == b"value" {
                     in_value = false;
+                } else if e.name().as_ref() == b"comment" {
+                    in_comment = false;
                 }
             }
             Ok(Event::Eof) => break,
@@ -67,274 +248,975 @@ pub fn parse_resx(path: &Path) -> Result<HashMap<String, String>> {
         buf.clear();
     }
 
-    Ok(entries)
+    Ok(result)
 }
 
-pub fn update_resx_key(path: &Path, key: &str, new_value: &str) -> Result<()> {
-    // We read the file and write to a temporary buffer/file, modifying the specific value
-    // This preserves comments and other structure usually.
-    // However, quick-xml event passing is tricky to get perfect round-trip (e.g. self-closing tags vs separate).
-    // For ResX, correct structure is crucial.
-    
-    // Strategy: Read file into memory (string), find the specific <data name="key"> block, replace value.
-    // If we use XML parser for rewriting, we ensure correctness but might change formatting.
-    // Given ".net resx resource manager", users might care about diffs.
-    // Let's try XML rewriting. If it's too destructive, we can switch to regex/string manipulation later.
-    
-    let content = fs::read_to_string(path)?;
-    let mut reader = Reader::from_str(&content);
-    reader.config_mut().trim_text(false); // Preserve whitespace for round-trip
+/// Returns the value of `key`, or `None` if the file doesn't contain it.
+/// Stops reading as soon as the key has been found.
+pub fn get_value(path: &Path, key: &str) -> Result<Option<String>> {
+    Ok(get_value_with_comment(path, key)?.map(|(value, _)| value))
+}
 
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
-    let mut buf = Vec::new();
+/// Like `get_value`, but also returns the entry's `<comment>`, if any.
+pub fn get_value_with_comment(path: &Path, key: &str) -> Result<Option<(String, Option<String>)>> {
+    let mut reader = Reader::from_file(path).context("Failed to open file")?;
+    reader.config_mut().trim_text(false);
 
-    let mut inside_target_data = false;
-    let mut inside_value = false;
-    let mut skip_text = false;
+    let mut buf = Vec::new();
+    let mut in_target = false;
+    let mut value = String::new();
+    let mut comment: Option<String> = None;
+    // Which child of the target <data> we're in: b"value", b"comment" or neither
+    let mut current_child: Option<Vec<u8>> = None;
 
     loop {
-        let event = reader.read_event_into(&mut buf);
-        match event {
+        match reader.read_event_into(&mut buf) {
             Ok(Event::Start(ref e)) => {
-                let name = e.name();
-                if name.as_ref() == b"data" {
-                    // Check if this is the target key
-                     for attr in e.attributes() {
-                        let attr = attr?;
-                        if attr.key.as_ref() == b"name" && attr.unescape_value()? == key {
-                            inside_target_data = true;
-                            break;
-                        }
+                if e.name().as_ref() == b"data" {
+                    in_target = match e.try_get_attribute("name")? {
+                        Some(attr) => attr.unescape_value()? == key,
+                        None => false,
+                    };
+                } else if in_target && (e.name().as_ref() == b"value" || e.name().as_ref() == b"comment") {
+                    if e.name().as_ref() == b"comment" {
+                        comment = Some(String::new());
                     }
-                    writer.write_event(Event::Start(e.clone()))?;
-                } else if name.as_ref() == b"value" && inside_target_data {
-                    inside_value = true;
-                    writer.write_event(Event::Start(e.clone()))?;
-                    
-                    // Write new value immediately
-                    let escaped = minimal_escape(new_value);
-                    let replacement = quick_xml::events::BytesText::from_escaped(escaped);
-                    writer.write_event(Event::Text(replacement))?;
-                    skip_text = true;
-                } else {
-                    writer.write_event(Event::Start(e.clone()))?;
+                    current_child = Some(e.name().as_ref().to_vec());
                 }
             }
-            Ok(Event::Text(ref e)) => {
-                if inside_value {
-                    if !skip_text {
-                         // Should not happen if we set skip_text=true immediately
-                         // But if we didn't write it yet? No, we did.
-                         // Just ignore original text
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                if let Some(attr) = e.try_get_attribute("name")? {
+                    if attr.unescape_value()? == key {
+                        return Ok(Some((String::new(), None)));
                     }
-                } else {
-                    writer.write_event(Event::Text(e.clone()))?;
                 }
             }
-            Ok(Event::End(ref e)) => {
-                if e.name().as_ref() == b"value" {
-                     inside_value = false;
-                     skip_text = false;
-                } else if e.name().as_ref() == b"data" {
-                    inside_target_data = false;
+            Ok(Event::Text(e)) if in_target => {
+                match current_child.as_deref() {
+                    Some(b"value") => value.push_str(&e.unescape()?),
+                    Some(b"comment") => comment.get_or_insert_with(String::new).push_str(&e.unescape()?),
+                    _ => {}
                 }
-                writer.write_event(Event::End(e.clone()))?;
             }
-            Ok(Event::Eof) => break,
-            Ok(e) => {
-                 writer.write_event(e)?;
+            Ok(Event::CData(e)) if in_target => {
+                match current_child.as_deref() {
+                    Some(b"value") => value.push_str(std::str::from_utf8(&e)?),
+                    Some(b"comment") => comment.get_or_insert_with(String::new).push_str(std::str::from_utf8(&e)?),
+                    _ => {}
+                }
             }
-            Err(e) => return Err(anyhow::anyhow!("XML Error: {:?}", e)),
+            Ok(Event::End(ref e)) if in_target => {
+                if e.name().as_ref() == b"data" {
+                    return Ok(Some((value, comment)));
+                }
+                current_child = None;
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow::anyhow!("Error at position {}: {:?}", reader.buffer_position(), e)),
+            _ => (),
         }
         buf.clear();
     }
 
-    let result = writer.into_inner().into_inner();
-    fs::write(path, result)?;
+    Ok(None)
+}
 
-    Ok(())
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ResxEntry {
+    pub key: String,
+    pub value: String,
+    pub comment: Option<String>,
 }
 
-pub fn update_resx_keys(path: &Path, updates: &HashMap<String, String>) -> Result<()> {
-    let content = fs::read_to_string(path)?;
-    let mut reader = Reader::from_str(&content);
-    reader.config_mut().trim_text(false);
+/// Like `parse_resx`, but returns the entries in file order together with their `<comment>`.
+pub fn parse_resx_with_comments(path: &Path) -> Result<Vec<ResxEntry>> {
+    let mut parsed = parse_resx(path)?;
+    Ok(parsed.entries.into_iter()
+        .map(|(key, value)| {
+            let comment = parsed.comments.remove(&key);
+            ResxEntry { key, value, comment }
+        })
+        .collect())
+}
 
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
+/// Returns the key of every `<data>` element in file order, without reading any values.
+pub fn parse_resx_keys(path: &Path) -> Result<Vec<String>> {
+    let mut reader = Reader::from_file(path).context("Failed to open file")?;
     let mut buf = Vec::new();
-
-    let mut current_key = String::new();
-    let mut inside_target_data = false;
-    let mut inside_value = false;
-    let mut skip_text = false;
+    let mut keys = Vec::new();
 
     loop {
-        let event = reader.read_event_into(&mut buf);
-        match event {
-            Ok(Event::Start(ref e)) => {
-                let name = e.name();
-                if name.as_ref() == b"data" {
-                     let mut is_target = false;
-                     for attr in e.attributes() {
-                        let attr = attr?;
-                        if attr.key.as_ref() == b"name" {
-                            let key_val = attr.unescape_value()?;
-                            if updates.contains_key(key_val.as_ref()) {
-                                current_key = key_val.to_string();
-                                is_target = true;
-                            }
-                        }
-                    }
-                    
-                    if is_target {
-                        inside_target_data = true;
-                    }
-                    writer.write_event(Event::Start(e.clone()))?;
-                } else if name.as_ref() == b"value" && inside_target_data {
-                    inside_value = true;
-                    writer.write_event(Event::Start(e.clone()))?;
-                    
-                    if let Some(new_val) = updates.get(&current_key) {
-                        let escaped = minimal_escape(new_val);
-                        let replacement = quick_xml::events::BytesText::from_escaped(escaped);
-                        writer.write_event(Event::Text(replacement))?;
-                        skip_text = true;
-                    }
-                } else {
-                    writer.write_event(Event::Start(e.clone()))?;
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                if let Some(attr) = e.try_get_attribute("name")? {
+                    keys.push(attr.unescape_value()?.to_string());
                 }
             }
-            Ok(Event::Text(ref e)) => {
-                if inside_value && skip_text {
-                     // Skip original text
-                } else {
-                    writer.write_event(Event::Text(e.clone()))?;
-                }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow::anyhow!("Error at position {}: {:?}", reader.buffer_position(), e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(keys)
+}
+
+/// Error produced while streaming entries with `ResxKeyIterator`, verifying a write or
+/// editing a key that doesn't exist.
+#[derive(Debug)]
+pub enum ResxError {
+    Io(std::io::Error),
+    Xml { position: u64, message: String },
+    /// A write was re-read and didn't produce the expected result.
+    VerificationFailed { expected: String, actual: String },
+    KeyNotFound(String),
+}
+
+impl std::fmt::Display for ResxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResxError::Io(e) => write!(f, "{}", e),
+            ResxError::Xml { position, message } => write!(f, "Error at position {}: {}", position, message),
+            ResxError::VerificationFailed { expected, actual } => {
+                write!(f, "Verification failed: expected {}, found {}", expected, actual)
             }
-            Ok(Event::End(ref e)) => {
-                if e.name().as_ref() == b"value" {
-                     inside_value = false;
-                     skip_text = false;
-                } else if e.name().as_ref() == b"data" {
-                    inside_target_data = false;
-                    current_key.clear();
+            ResxError::KeyNotFound(key) => write!(f, "Key '{}' not found", key),
+        }
+    }
+}
+
+impl std::error::Error for ResxError {}
+
+impl From<std::io::Error> for ResxError {
+    fn from(e: std::io::Error) -> Self {
+        ResxError::Io(e)
+    }
+}
+
+/// Streams the `(key, value)` pairs of a resx file in file order, reading only as much of the
+/// file as needed, so callers can stop early with `find`, `take` and friends.
+/// Entries with an empty key are skipped; after an error the iterator is exhausted.
+pub struct ResxKeyIterator {
+    reader: Reader<std::io::BufReader<fs::File>>,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl ResxKeyIterator {
+    pub fn open(path: &Path) -> std::result::Result<Self, ResxError> {
+        let file = fs::File::open(path)?;
+        let mut reader = Reader::from_reader(std::io::BufReader::new(file));
+        reader.config_mut().trim_text(false);
+        Ok(Self { reader, buf: Vec::new(), done: false })
+    }
+
+    fn xml_error(&mut self, message: String) -> ResxError {
+        self.done = true;
+        ResxError::Xml { position: self.reader.buffer_position(), message }
+    }
+
+    fn data_name(&mut self, e: &BytesStart) -> std::result::Result<String, ResxError> {
+        let name = e.try_get_attribute("name")
+            .and_then(|attr| attr.map(|a| a.unescape_value().map(|v| v.to_string())).transpose());
+        match name {
+            Ok(name) => Ok(name.unwrap_or_default()),
+            Err(e) => Err(self.xml_error(format!("{:?}", e))),
+        }
+    }
+
+    fn read_entry(&mut self) -> std::result::Result<Option<(String, String)>, ResxError> {
+        let mut key: Option<String> = None;
+        let mut value = String::new();
+        let mut in_value = false;
+
+        loop {
+            self.buf.clear();
+            let event = match self.reader.read_event_into(&mut self.buf) {
+                Ok(event) => event.into_owned(),
+                Err(e) => return Err(self.xml_error(format!("{:?}", e))),
+            };
+            match event {
+                Event::Start(e) if e.name().as_ref() == b"data" => {
+                    key = Some(self.data_name(&e)?);
+                    value.clear();
                 }
-                writer.write_event(Event::End(e.clone()))?;
-            }
-            Ok(Event::Eof) => break,
-            Ok(e) => {
-                 writer.write_event(e)?;
+                // <data name="X"/> is an entry with an empty value
+                Event::Empty(e) if e.name().as_ref() == b"data" => {
+                    let name = self.data_name(&e)?;
+                    if !name.is_empty() {
+                        return Ok(Some((name, String::new())));
+                    }
+                }
+                Event::Start(e) if e.name().as_ref() == b"value" && key.is_some() => in_value = true,
+                Event::Text(e) if in_value => match e.unescape() {
+                    Ok(text) => value.push_str(&text),
+                    Err(e) => return Err(self.xml_error(format!("{:?}", e))),
+                },
+                Event::CData(e) if in_value => value.push_str(&String::from_utf8_lossy(&e)),
+                Event::End(e) if e.name().as_ref() == b"value" => in_value = false,
+                Event::End(e) if e.name().as_ref() == b"data" => {
+                    match key.take() {
+                        Some(k) if !k.is_empty() => return Ok(Some((k, std::mem::take(&mut value)))),
+                        _ => value.clear(),
+                    }
+                }
+                Event::Eof => {
+                    self.done = true;
+                    return Ok(None);
+                }
+                _ => {}
             }
-            Err(e) => return Err(anyhow::anyhow!("XML Error: {:?}", e)),
         }
-        buf.clear();
     }
+}
 
-    let result = writer.into_inner().into_inner();
-    fs::write(path, result)?;
+impl Iterator for ResxKeyIterator {
+    type Item = std::result::Result<(String, String), ResxError>;
 
-    Ok(())
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.read_entry().transpose()
+    }
 }
 
-pub fn rename_resx_key(path: &Path, old_key: &str, new_key: &str) -> Result<()> {
-    let content = fs::read_to_string(path)?;
-    let mut reader = Reader::from_str(&content);
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct FileStatistics {
+    pub key_count: usize,
+    /// Value lengths are in characters; all three are 0 for a file without keys.
+    pub average_value_length: f64,
+    pub max_value_length: usize,
+    pub min_value_length: usize,
+    pub empty_value_count: usize,
+    pub comment_count: usize,
+    /// Whether any entry has a `type` attribute, i.e. is an image or other non-string resource.
+    pub has_binary_entries: bool,
+    pub file_size_bytes: u64,
+}
+
+/// Computes `FileStatistics` while streaming through the file once, without keeping the values.
+pub fn file_statistics(path: &Path) -> Result<FileStatistics> {
+    let mut stats = FileStatistics { file_size_bytes: fs::metadata(path)?.len(), min_value_length: usize::MAX, ..Default::default() };
+    let mut reader = Reader::from_file(path).context("Failed to open file")?;
     reader.config_mut().trim_text(false);
 
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
     let mut buf = Vec::new();
+    let mut total_length = 0;
+    let mut value_length = 0;
+    let mut in_data = false;
+    let mut in_value = false;
 
     loop {
-        let event = reader.read_event_into(&mut buf);
-        match event {
-            Ok(Event::Start(ref e)) => {
-                if e.name().as_ref() == b"data" {
-                    let mut elem = e.clone();
-                    let mut attributes = e.attributes().collect::<Result<Vec<_>, _>>()?;
-                    let mut found = false;
-                    
-                    for attr in &mut attributes {
-                        if attr.key.as_ref() == b"name" && attr.unescape_value()? == old_key {
-                            // Replace the value of the name attribute
-                            // quick-xml doesn't make it super easy to modify attributes in place on the event
-                            // We have to reconstruct the element or attributes
-                            found = true;
-                        }
-                    }
-
-                    if found {
-                        // Reconstruct attributes with new name
-                        elem.clear_attributes();
-                        for attr in attributes {
-                            if attr.key.as_ref() == b"name" {
-                                elem.push_attribute(("name", new_key));
-                            } else {
-                                elem.push_attribute(attr);
-                            }
-                        }
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.name().as_ref() {
+                b"data" => {
+                    in_data = true;
+                    value_length = 0;
+                    if e.try_get_attribute("type")?.is_some() {
+                        stats.has_binary_entries = true;
                     }
-                    writer.write_event(Event::Start(elem))?;
-                } else {
-                    writer.write_event(Event::Start(e.clone()))?;
+                }
+                b"value" if in_data => in_value = true,
+                b"comment" if in_data => stats.comment_count += 1,
+                _ => {}
+            },
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                stats.key_count += 1;
+                stats.min_value_length = 0;
+                stats.empty_value_count += 1;
+                if e.try_get_attribute("type")?.is_some() {
+                    stats.has_binary_entries = true;
                 }
             }
+            Ok(Event::Text(e)) if in_value => value_length += e.unescape()?.chars().count(),
+            Ok(Event::CData(e)) if in_value => value_length += std::str::from_utf8(&e)?.chars().count(),
+            Ok(Event::End(ref e)) => match e.name().as_ref() {
+                b"value" => in_value = false,
+                b"data" if in_data => {
+                    in_data = false;
+                    stats.key_count += 1;
+                    total_length += value_length;
+                    stats.max_value_length = stats.max_value_length.max(value_length);
+                    stats.min_value_length = stats.min_value_length.min(value_length);
+                    if value_length == 0 {
+                        stats.empty_value_count += 1;
+                    }
+                }
+                _ => {}
+            },
             Ok(Event::Eof) => break,
-            Ok(e) => {
-                 writer.write_event(e)?;
-            }
-            Err(e) => return Err(anyhow::anyhow!("XML Error: {:?}", e)),
+            Err(e) => return Err(anyhow::anyhow!("Error at position {}: {:?}", reader.buffer_position(), e)),
+            _ => (),
         }
         buf.clear();
     }
 
-    let result = writer.into_inner().into_inner();
-    fs::write(path, result)?;
+    if stats.key_count == 0 {
+        stats.min_value_length = 0;
+    } else {
+        stats.average_value_length = total_length as f64 / stats.key_count as f64;
+    }
+    Ok(stats)
+}
 
-    Ok(())
+/// Returns whether `key` exists, stopping at the first match.
+pub fn key_exists(path: &Path, key: &str) -> Result<bool> {
+    for entry in ResxKeyIterator::open(path)? {
+        if entry?.0 == key {
+            return Ok(true);
+        }
+    }
+    Ok(false)
 }
 
-pub fn add_resx_key(path: &Path, key: &str, value: &str) -> Result<()> {
-    // Simple append approach: read, find </root>, insert before it.
-    // This is robust enough for valid XML.
-    let content = fs::read_to_string(path)?;
-    // Check if key exists first
-    if content.contains(&format!("name=\"{}\"", key)) {
-         return Err(anyhow::anyhow!("Key already exists"));
+/// Counts the entries of a file, stopping once `limit` entries have been seen.
+pub fn count_keys_streaming(path: &Path, limit: Option<usize>) -> Result<usize> {
+    let mut count = 0;
+    for entry in ResxKeyIterator::open(path)?.take(limit.unwrap_or(usize::MAX)) {
+        entry?;
+        count += 1;
     }
+    Ok(count)
+}
 
-    let escaped_value = minimal_escape(value);
-    let entry = format!(
-        "\n    <data name=\"{}\" xml:space=\"preserve\">\n        <value>{}</value>\n    </data>",
-        key, escaped_value
-    );
+/// Re-reads `key` after a write and checks that it has the `expected` value, or that it's
+/// gone if `expected` is `None`.
+fn verify_entry(path: &Path, key: &str, expected: Option<&str>) -> Result<()> {
+    check_entry(key, expected, get_value(path, key)?.as_deref())
+}
 
-    let new_content = if let Some(idx) = content.rfind("</root>") {
-        let (start, end) = content.split_at(idx);
-        format!("{}{}\n{}", start.trim_end(), entry, end)
-    } else {
-        // Fallback or error
-        format!("{} \n<root>\n{}\\n</root>", content, entry) 
-    };
-    
-    fs::write(path, new_content)?;
+/// `verify_entry` for several keys, re-reading the file once.
+fn verify_entries<'a>(path: &Path, expected: impl IntoIterator<Item = (&'a str, Option<&'a str>)>) -> Result<()> {
+    let entries = parse_resx(path)?.entries;
+    for (key, expected) in expected {
+        check_entry(key, expected, entries.get(key).map(String::as_str))?;
+    }
     Ok(())
 }
 
-pub fn remove_resx_keys(path: &Path, keys: &std::collections::HashSet<String>) -> Result<HashMap<String, usize>> {
-    let content = fs::read_to_string(path)?;
-    let has_bom = content.starts_with('\u{feff}');
-    let mut reader = Reader::from_str(&content);
-    reader.config_mut().trim_text(false); 
-
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
-    let mut buf = Vec::new();
+fn check_entry(key: &str, expected: Option<&str>, actual: Option<&str>) -> Result<()> {
+    if actual != expected {
+        let describe = |value: Option<&str>| match value {
+            Some(value) => format!("'{}' = '{}'", key, value),
+            None => format!("no key '{}'", key),
+        };
+        return Err(ResxError::VerificationFailed { expected: describe(expected), actual: describe(actual) }.into());
+    }
+    Ok(())
+}
 
-    let mut inside_target_data = false;
-    let mut pending_whitespace: Option<Event> = None;
-    
-    let mut current_index = 0;
-    let mut removed_indices = HashMap::new();
-    let mut current_key = String::new();
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// Both kinds occur; such files are written back without converting anything.
+    Mixed,
+}
+
+/// Detects which line endings `content` uses. Content without any line breaks counts as LF.
+pub fn detect_line_endings(content: &str) -> LineEnding {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (0, _) => LineEnding::Lf,
+        (_, 0) => LineEnding::Crlf,
+        _ => LineEnding::Mixed,
+    }
+}
+
+/// Gives every line break in `value` the `line_ending`.
+fn with_line_ending(value: &str, line_ending: &str) -> String {
+    value.replace("\r\n", "\n").replace('\n', line_ending)
+}
+
+/// Replaces the value of `key` and returns the number of bytes written. Fails with
+/// `ResxError::KeyNotFound` if the file has no such key; use `add_resx_key` to add it instead.
+/// See `update_resx_keys` for `preserve_cdata` and `options`.
+/// With `verify` set, the file is re-read afterwards to check the key has the new value.
+pub fn update_resx_key(path: &Path, key: &str, new_value: &str, preserve_cdata: bool, verify: bool, options: &ResxWriteOptions) -> Result<usize> {
+    let report = update_resx_keys(path, &HashMap::from([(key.to_string(), new_value.to_string())]), preserve_cdata, verify, options)?;
+    if !report.not_found.is_empty() {
+        return Err(ResxError::KeyNotFound(key.to_string()).into());
+    }
+    Ok(report.bytes_written)
+}
+
+/// Clears values that consist only of whitespace, which are almost always accidental, and returns
+/// their keys sorted. With `dry_run` set, the keys are only listed.
+pub fn clear_whitespace_only_values(path: &Path, dry_run: bool) -> Result<Vec<String>> {
+    let updates: HashMap<String, String> = parse_resx(path)?.entries.into_iter()
+        .filter(|(_, value)| !value.is_empty() && value.trim().is_empty())
+        .map(|(key, _)| (key, String::new()))
+        .collect();
+    let mut keys: Vec<String> = updates.keys().cloned().collect();
+    keys.sort();
+    if !dry_run && !updates.is_empty() {
+        update_resx_keys(path, &updates, true, false, &ResxWriteOptions::default())?;
+    }
+    Ok(keys)
+}
+
+/// Outcome of `update_resx_keys`: which of the requested keys were updated and which
+/// don't exist in the file. Keys are listed in file order and sorted order respectively.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct BatchUpdateReport {
+    pub updated: Vec<String>,
+    pub not_found: Vec<String>,
+    pub bytes_written: usize,
+}
+
+/// Updates the values of all `updates` keys in a single pass. Keys the file doesn't contain
+/// are skipped and reported in `not_found`; the file is left untouched if none were found.
+/// If an existing value is wrapped in a CDATA section and `preserve_cdata` is set,
+/// the new value is written as CDATA too; otherwise it is escaped text.
+/// Line breaks in the new values follow `options.newline`; with `NewlineStyle::Preserve`, they
+/// become CRLF in a CRLF file and are kept as given otherwise.
+/// A key whose `<data>` has no `<value>`, or is self-closing, gets one.
+/// With `verify` set, the file is re-read afterwards to check the updated keys have their new values.
+pub fn update_resx_keys(path: &Path, updates: &HashMap<String, String>, preserve_cdata: bool, verify: bool, options: &ResxWriteOptions) -> Result<BatchUpdateReport> {
+    let content = fs::read_to_string(path)?;
+    let updates: HashMap<String, String> = updates.iter()
+        .map(|(key, value)| (key.clone(), options.normalize_value(&content, value)))
+        .collect();
+    let mut reader = Reader::from_str(&content);
+    reader.config_mut().trim_text(false); // Preserve whitespace for round-trip
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut buf = Vec::new();
+
+    // The new value of the <data> element being read, if it is one of the updated keys
+    let mut target_value: Option<&str> = None;
+    let mut inside_value = false;
+    let mut had_cdata = false;
+    let mut value_seen = false;
+    // Until a <value> is seen, whitespace inside the target element is held back, so that a
+    // missing value can be inserted on its own line before the closing tag
+    let mut pending_whitespace: Option<BytesText<'static>> = None;
+    let mut child_indent: Option<BytesText<'static>> = None;
+    let mut matched: HashSet<String> = HashSet::new();
+    let mut report = BatchUpdateReport::default();
+
+    let mut match_key = |e: &BytesStart, report: &mut BatchUpdateReport| -> Result<Option<&str>> {
+        let key = data_attributes(e)?.0;
+        Ok(updates.get_key_value(&key).map(|(key, value)| {
+            if matched.insert(key.clone()) {
+                report.updated.push(key.clone());
+            }
+            value.as_str()
+        }))
+    };
+    let write_value = |writer: &mut Writer<Cursor<Vec<u8>>>, value: &str| {
+        writer.create_element("value").write_text_content(BytesText::from_escaped(minimal_escape(value))).map(|_| ())
+    };
+
+    loop {
+        let event_start = bom_len(&content) + reader.buffer_position() as usize;
+        let event = reader.read_event_into(&mut buf);
+        let holds_whitespace = target_value.is_some() && !value_seen
+            && matches!(&event, Ok(Event::Text(e)) if e.iter().all(u8::is_ascii_whitespace));
+        let closes_data = matches!(&event, Ok(Event::End(e)) if e.name().as_ref() == b"data");
+        if !holds_whitespace && !closes_data {
+            if let Some(whitespace) = pending_whitespace.take() {
+                writer.write_event(Event::Text(whitespace))?;
+            }
+        }
+        match event {
+            Ok(Event::Start(ref e)) => {
+                let name = e.name();
+                if name.as_ref() == b"data" {
+                    target_value = match_key(e, &mut report)?;
+                    value_seen = false;
+                    child_indent = None;
+                } else if name.as_ref() == b"value" && target_value.is_some() {
+                    // The new value is written when </value> is reached, once we know
+                    // whether the original content was a CDATA section.
+                    inside_value = true;
+                    had_cdata = false;
+                    value_seen = true;
+                }
+                writer.write_event(Event::Start(e.clone()))?;
+            }
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                match match_key(e, &mut report)? {
+                    Some(value) => {
+                        // Expand <data name="..."/> into an element with a value
+                        let line = &content[content[..event_start].rfind('\n').map_or(0, |i| i + 1)..];
+                        let closing_indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+                        let line_ending = options.line_ending(&content);
+                        let value_indent = format!("{}{}{}", line_ending, closing_indent, options.indent_unit(&content));
+                        writer.write_event(Event::Start(expanded_start(e)))?;
+                        writer.write_event(Event::Text(BytesText::from_escaped(value_indent)))?;
+                        write_value(&mut writer, value)?;
+                        writer.write_event(Event::Text(BytesText::from_escaped(format!("{}{}", line_ending, closing_indent))))?;
+                        writer.write_event(Event::End(BytesEnd::new("data")))?;
+                    }
+                    None => writer.write_event(Event::Empty(e.clone()))?,
+                }
+            }
+            Ok(Event::Text(ref e)) => {
+                if holds_whitespace {
+                    let whitespace = e.clone().into_owned();
+                    child_indent.get_or_insert_with(|| whitespace.clone());
+                    if let Some(previous) = pending_whitespace.replace(whitespace) {
+                        writer.write_event(Event::Text(previous))?;
+                    }
+                } else if !inside_value {
+                    writer.write_event(Event::Text(e.clone()))?;
+                }
+            }
+            Ok(Event::CData(ref e)) => {
+                if inside_value {
+                    had_cdata = true;
+                } else {
+                    writer.write_event(Event::CData(e.clone()))?;
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                if e.name().as_ref() == b"value" && inside_value {
+                    let new_value = target_value.unwrap_or_default();
+                    // A CDATA section cannot contain its own terminator, so fall back to escaped text
+                    if had_cdata && preserve_cdata && !new_value.contains("]]>") {
+                        writer.write_event(Event::CData(BytesCData::new(new_value)))?;
+                    } else {
+                        let escaped = minimal_escape(new_value);
+                        writer.write_event(Event::Text(BytesText::from_escaped(escaped)))?;
+                    }
+                    inside_value = false;
+                } else if e.name().as_ref() == b"data" {
+                    if let Some(value) = target_value.filter(|_| !value_seen) {
+                        if let Some(indent) = child_indent.take() {
+                            writer.write_event(Event::Text(indent))?;
+                        }
+                        write_value(&mut writer, value)?;
+                    }
+                    if let Some(whitespace) = pending_whitespace.take() {
+                        writer.write_event(Event::Text(whitespace))?;
+                    }
+                    target_value = None;
+                }
+                writer.write_event(Event::End(e.clone()))?;
+            }
+            Ok(Event::Eof) => break,
+            // Processing instructions, the doctype and comments never belong to a value, so they are copied as-is
+            Ok(e) => {
+                writer.write_event(e)?;
+            }
+            Err(e) => return Err(anyhow::anyhow!("XML Error: {:?}", e)),
+        }
+        buf.clear();
+    }
+
+    report.not_found = updates.keys().filter(|k| !matched.contains(*k)).cloned().collect();
+    report.not_found.sort();
+    if report.updated.is_empty() {
+        return Ok(report);
+    }
+
+    let result = keep_bom(writer.into_inner().into_inner(), has_bom(path));
+    write_atomically(path, &result)?;
+    report.bytes_written = result.len();
+    if verify {
+        verify_entries(path, report.updated.iter().map(|key| (key.as_str(), Some(updates[key].as_str()))))?;
+    }
+
+    Ok(report)
+}
+
+/// Sets the `<comment>` of `key`, or removes it if `comment` is `None`. A new comment goes after
+/// the value, indented like it; the rest of the element is kept as it is.
+pub fn update_resx_comment(path: &Path, key: &str, comment: Option<&str>) -> Result<()> {
+    let content = fs::read_to_string(path)?;
+    let mut reader = Reader::from_str(&content);
+    reader.config_mut().trim_text(false);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+
+    let mut found = false;
+    let mut in_target = false;
+    let mut in_old_comment = false;
+    // Whitespace inside the target element is held back until we know whether it precedes the
+    // old comment, which takes its line with it
+    let mut pending_whitespace: Option<BytesText> = None;
+    let mut child_indent: Option<BytesText> = None;
+
+    loop {
+        let event = reader.read_event()?;
+        if in_old_comment {
+            in_old_comment = !matches!(&event, Event::End(e) if e.name().as_ref() == b"comment");
+            continue;
+        }
+        match event {
+            Event::Start(ref e) if e.name().as_ref() == b"data" => {
+                in_target = match e.try_get_attribute("name")? {
+                    Some(attr) => attr.unescape_value()? == key,
+                    None => false,
+                };
+                found |= in_target;
+                child_indent = None;
+                writer.write_event(event)?;
+            }
+            Event::Text(e) if in_target && e.iter().all(u8::is_ascii_whitespace) => {
+                child_indent.get_or_insert_with(|| e.clone());
+                if let Some(previous) = pending_whitespace.replace(e) {
+                    writer.write_event(Event::Text(previous))?;
+                }
+            }
+            Event::Start(ref e) | Event::Empty(ref e) if in_target && e.name().as_ref() == b"comment" => {
+                in_old_comment = matches!(event, Event::Start(_));
+                pending_whitespace = None;
+            }
+            Event::End(ref e) if in_target && e.name().as_ref() == b"data" => {
+                if let Some(comment) = comment {
+                    if let Some(indent) = child_indent.take() {
+                        writer.write_event(Event::Text(indent))?;
+                    }
+                    writer.create_element("comment").write_text_content(BytesText::from_escaped(minimal_escape(comment)))?;
+                }
+                if let Some(whitespace) = pending_whitespace.take() {
+                    writer.write_event(Event::Text(whitespace))?;
+                }
+                in_target = false;
+                writer.write_event(event)?;
+            }
+            Event::Eof => break,
+            event => {
+                if let Some(whitespace) = pending_whitespace.take() {
+                    writer.write_event(Event::Text(whitespace))?;
+                }
+                writer.write_event(event)?;
+            }
+        }
+    }
+
+    if !found {
+        return Err(ResxError::KeyNotFound(key.to_string()).into());
+    }
+    write_atomically(path, &keep_bom(writer.into_inner().into_inner(), has_bom(path)))
+}
+
+/// Renames `old_key` to `new_key`. With `verify` set, the file is re-read afterwards to check
+/// that `old_key` is gone and `new_key` has its value. Renaming a key to itself does nothing.
+/// Fails without writing if `old_key` doesn't exist or `new_key` already does.
+pub fn rename_resx_key(path: &Path, old_key: &str, new_key: &str, verify: bool) -> Result<usize> {
+    if new_key.is_empty() {
+        return Err(anyhow::anyhow!("New key name cannot be empty"));
+    }
+    if old_key == new_key {
+        return Ok(0);
+    }
+    let keys = parse_resx_keys(path)?;
+    if !keys.iter().any(|k| k == old_key) {
+        return Err(ResxError::KeyNotFound(old_key.to_string()).into());
+    }
+    if keys.iter().any(|k| k == new_key) {
+        return Err(anyhow::anyhow!("Key '{}' already exists", new_key));
+    }
+    let old_value = if verify { get_value(path, old_key)? } else { None };
+    let (bytes_written, renamed) = rename_keys(path, &HashMap::from([(old_key.to_string(), new_key.to_string())]))?;
+    if renamed.is_empty() {
+        return Err(ResxError::KeyNotFound(old_key.to_string()).into());
+    }
+    if verify {
+        verify_entry(path, old_key, None)?;
+        verify_entry(path, new_key, old_value.as_deref())?;
+    }
+
+    Ok(bytes_written)
+}
+
+/// Renames every key in `renames` (old -> new) in a single pass. Returns the number of bytes
+/// written and the old keys that were found; the file is left untouched if there were none.
+fn rename_keys(path: &Path, renames: &HashMap<String, String>) -> Result<(usize, HashSet<String>)> {
+    let content = fs::read_to_string(path)?;
+    let mut reader = Reader::from_str(&content);
+    reader.config_mut().trim_text(false);
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut buf = Vec::new();
+    let mut renamed = HashSet::new();
+
+    // quick-xml can't change an attribute in place, so a renamed element is rebuilt with the new name
+    let mut rename_data = |e: &BytesStart| -> Result<BytesStart<'static>> {
+        let mut elem = e.clone().into_owned();
+        let attributes = e.attributes().collect::<Result<Vec<_>, _>>()?;
+        let mut new_key = None;
+        for attr in &attributes {
+            if attr.key.as_ref() == b"name" {
+                let old_key = attr.unescape_value()?;
+                if let Some(new) = renames.get(old_key.as_ref()) {
+                    renamed.insert(old_key.to_string());
+                    new_key = Some(new);
+                }
+            }
+        }
+
+        if let Some(new_key) = new_key {
+            elem.clear_attributes();
+            for attr in attributes {
+                if attr.key.as_ref() == b"name" {
+                    elem.push_attribute(("name", new_key.as_str()));
+                } else {
+                    elem.push_attribute(attr);
+                }
+            }
+        }
+        Ok(elem)
+    };
+
+    loop {
+        let event = reader.read_event_into(&mut buf);
+        match event {
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"data" => {
+                writer.write_event(Event::Start(rename_data(e)?))?;
+            }
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                writer.write_event(Event::Empty(rename_data(e)?))?;
+            }
+            Ok(Event::End(ref e)) => {
+                writer.write_event(Event::End(e.clone()))?;
+            }
+            Ok(Event::Eof) => break,
+            Ok(e) => {
+                 writer.write_event(e)?;
+            }
+            Err(e) => return Err(anyhow::anyhow!("XML Error: {:?}", e)),
+        }
+        buf.clear();
+    }
+
+    if renamed.is_empty() {
+        return Ok((0, renamed));
+    }
+    let result = keep_bom(writer.into_inner().into_inner(), has_bom(path));
+    write_atomically(path, &result)?;
+    Ok((result.len(), renamed))
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ConvertReport {
+    /// (old key, new key), in file order.
+    pub renamed: Vec<(String, String)>,
+    /// Keys left as they are because their new name is already taken, or would be taken by another renamed key.
+    pub skipped_conflicts: Vec<String>,
+}
+
+/// Renames every key containing `separator` to use `target_separator` instead, e.g. "Button/Save"
+/// to "Button.Save". Conflicts are found before anything is written and the keys involved are skipped.
+pub fn convert_key_separators(path: &Path, separator: char, target_separator: char) -> Result<ConvertReport> {
+    if separator == target_separator {
+        return Err(anyhow::anyhow!("The separators must differ"));
+    }
+    let keys = parse_resx_keys(path)?;
+    let candidates: Vec<(String, String)> = keys.iter()
+        .filter(|key| key.contains(separator))
+        .map(|key| (key.clone(), key.replace(separator, &target_separator.to_string())))
+        .collect();
+    let unchanged: HashSet<&String> = keys.iter().filter(|key| !key.contains(separator)).collect();
+    let mut target_counts: HashMap<&String, usize> = HashMap::new();
+    for (_, new_key) in &candidates {
+        *target_counts.entry(new_key).or_default() += 1;
+    }
+
+    let mut report = ConvertReport::default();
+    for (old_key, new_key) in &candidates {
+        if unchanged.contains(new_key) || target_counts[new_key] > 1 {
+            if !report.skipped_conflicts.contains(old_key) {
+                report.skipped_conflicts.push(old_key.clone());
+            }
+        } else if !report.renamed.iter().any(|(old, _)| old == old_key) {
+            report.renamed.push((old_key.clone(), new_key.clone()));
+        }
+    }
+
+    if !report.renamed.is_empty() {
+        rename_keys(path, &report.renamed.iter().cloned().collect())?;
+    }
+    Ok(report)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum IndentStyle {
+    Spaces(usize),
+    Tabs,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum NewlineStyle {
+    Lf,
+    Crlf,
+    /// Use whatever the file already uses; LF for new files.
+    Preserve,
+}
+
+/// How new markup is formatted when writing. Existing content is always kept as it is;
+/// these only apply to elements that are added, to newly created files and to the line
+/// breaks of updated values.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ResxWriteOptions {
+    pub indent: IndentStyle,
+    /// Indent like the existing `<data>` elements of the file, falling back to `indent`.
+    pub preserve_original_indent: bool,
+    pub newline: NewlineStyle,
+    /// Start newly created files with an XML declaration.
+    pub xml_declaration: bool,
+}
+
+impl Default for ResxWriteOptions {
+    fn default() -> Self {
+        Self {
+            indent: IndentStyle::Spaces(4),
+            preserve_original_indent: true,
+            newline: NewlineStyle::Preserve,
+            xml_declaration: true,
+        }
+    }
+}
+
+impl ResxWriteOptions {
+    /// The line ending to use for new markup in a file with `content`.
+    pub fn line_ending(&self, content: &str) -> &'static str {
+        match self.newline {
+            NewlineStyle::Lf => "\n",
+            NewlineStyle::Crlf => "\r\n",
+            NewlineStyle::Preserve if content.contains("\r\n") => "\r\n",
+            NewlineStyle::Preserve => "\n",
+        }
+    }
+
+    /// `value` with the line breaks a new value in a file with `content` gets: those of `newline`,
+    /// or with `NewlineStyle::Preserve`, CRLF in a CRLF file and the given ones otherwise.
+    fn normalize_value(&self, content: &str, value: &str) -> String {
+        match self.newline {
+            NewlineStyle::Lf => with_line_ending(value, "\n"),
+            NewlineStyle::Crlf => with_line_ending(value, "\r\n"),
+            NewlineStyle::Preserve if detect_line_endings(content) == LineEnding::Crlf => with_line_ending(value, "\r\n"),
+            NewlineStyle::Preserve => value.to_string(),
+        }
+    }
+
+    /// One level of indentation for new markup in a file with `content`.
+    pub fn indent_unit(&self, content: &str) -> String {
+        if self.preserve_original_indent {
+            let existing = content.lines()
+                .map(|line| line.split_at(line.len() - line.trim_start().len()))
+                .find(|(ws, rest)| !ws.is_empty() && (rest.starts_with("<data") || rest.starts_with("<resheader")));
+            if let Some((ws, _)) = existing {
+                return ws.to_string();
+            }
+        }
+        match self.indent {
+            IndentStyle::Spaces(n) => " ".repeat(n),
+            IndentStyle::Tabs => "\t".to_string(),
+        }
+    }
+}
+
+/// Serializes a `<data>` element for `key` and `value`, escaping both. The `<value>` line is
+/// indented with `value_indent` and the closing `</data>` with `closing_indent`.
+fn data_element(key: &str, value: &str, comment: Option<&str>, line_ending: &str, closing_indent: &str, value_indent: &str) -> Result<String> {
+    let mut writer = Writer::new(Vec::new());
+    let mut start = BytesStart::new("data");
+    start.push_attribute(("name", key));
+    start.push_attribute(("xml:space", "preserve"));
+    writer.write_event(Event::Start(start))?;
+    writer.write_event(Event::Text(BytesText::from_escaped(format!("{}{}", line_ending, value_indent))))?;
+    writer.create_element("value").write_text_content(BytesText::from_escaped(minimal_escape(value)))?;
+    if let Some(comment) = comment {
+        writer.write_event(Event::Text(BytesText::from_escaped(format!("{}{}", line_ending, value_indent))))?;
+        writer.create_element("comment").write_text_content(BytesText::from_escaped(minimal_escape(comment)))?;
+    }
+    writer.write_event(Event::Text(BytesText::from_escaped(format!("{}{}", line_ending, closing_indent))))?;
+    writer.write_event(Event::End(BytesEnd::new("data")))?;
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// What `add_resx_key` does when the key is already in the file.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum IfExistsPolicy {
+    #[default]
+    Error,
+    /// Leave the file as it is.
+    Skip,
+    /// Set the existing key to the new value.
+    Update,
+}
+
+/// Appends `key` at the end of the file, with a `<comment>` if `comment` is given; `if_exists`
+/// decides what happens if it's already there. With `verify` set, the file is re-read afterwards
+/// to check the key exists with `value`. Returns the number of bytes written, 0 if nothing was.
+pub fn add_resx_key(path: &Path, key: &str, value: &str, comment: Option<&str>, if_exists: IfExistsPolicy, verify: bool, options: &ResxWriteOptions) -> Result<usize> {
+    if key_exists(path, key)? {
+        return match if_exists {
+            IfExistsPolicy::Error => Err(anyhow::anyhow!("Key already exists")),
+            IfExistsPolicy::Skip => Ok(0),
+            IfExistsPolicy::Update => {
+                let written = update_resx_key(path, key, value, true, verify, options)?;
+                if comment.is_some() {
+                    update_resx_comment(path, key, comment)?;
+                }
+                Ok(written)
+            }
+        };
+    }
+
+    // Streamed through the XML reader/writer rather than regenerated, so comments, the schema
+    // and any other content of the file are kept as they are.
+    let item = ResxInsert { key: key.to_string(), value: value.to_string(), comment: comment.map(str::to_string), index: usize::MAX };
+    insert_resx_keys(path, vec![item], options, verify)
+}
+
+/// The temporary file `write_atomically` writes to before renaming it over `path`.
+pub fn atomic_write_tmp_path(path: &Path) -> Option<PathBuf> {
+    path.file_name().map(|file_name| path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy())))
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it over `path`,
+/// so the file is never left half-written.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp_path = atomic_write_tmp_path(path).ok_or_else(|| anyhow::anyhow!("{} is not a file path", path.display()))?;
+    let written = fs::write(&tmp_path, contents).and_then(|_| fs::rename(&tmp_path, path));
+    #[cfg(windows)]
+    let written = written.or_else(|_| replace_by_copy(&tmp_path, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Fallback for Windows versions that can't rename over an existing file. The copy isn't atomic,
+/// so a lock file next to it keeps a second writer from copying over it at the same time.
+#[cfg(windows)]
+fn replace_by_copy(tmp_path: &Path, path: &Path) -> std::io::Result<()> {
+    let lock_path = tmp_path.with_extension("lock");
+    fs::OpenOptions::new().write(true).create_new(true).open(&lock_path)?;
+    let result = fs::copy(tmp_path, path).and_then(|_| fs::remove_file(tmp_path));
+    let _ = fs::remove_file(&lock_path);
+    result
+}
+
+/// Removes the `<data>` elements of all `keys` in a single pass and returns the index each
+/// removed key had. With `verify` set, the file is re-read afterwards to check the keys are gone.
+pub fn remove_resx_keys(path: &Path, keys: &HashSet<String>, verify: bool) -> Result<HashMap<String, usize>> {
+    let content = fs::read_to_string(path)?;
+    let has_bom = content.starts_with('\u{feff}');
+    let mut reader = Reader::from_str(&content);
+    reader.config_mut().trim_text(false); 
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut buf = Vec::new();
+
+    let mut inside_target_data = false;
+    let mut pending_whitespace: Option<Event> = None;
+    // Indentation that preceded the last removed block. It is dropped if the removed block was
+    // followed by whitespace of its own, but restored if a sibling followed it directly.
+    let mut removed_whitespace: Option<Event> = None;
+    
+    let mut current_index = 0;
+    let mut removed_indices = HashMap::new();
+    let mut current_key = String::new();
 
     loop {
         let event = reader.read_event_into(&mut buf);
@@ -361,24 +1243,40 @@ pub fn remove_resx_keys(path: &Path, keys: &std::collections::HashSet<String>) -
 
                 if is_target {
                     inside_target_data = true;
-                    // Discard pending whitespace
-                    pending_whitespace = None;
+                    removed_whitespace = pending_whitespace.take().or(removed_whitespace.take());
                 } else {
                     if !inside_target_data {
-                        if let Some(ws) = pending_whitespace.take() {
+                        if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                             writer.write_event(ws)?;
                         }
                         writer.write_event(Event::Start(e.clone()))?;
                     }
                 }
             }
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" && !inside_target_data => {
+                // A self-closing <data name="..."/> has no End event, so it is dropped right here
+                let target = match e.try_get_attribute("name")? {
+                    Some(attr) => Some(attr.unescape_value()?.to_string()).filter(|k| keys.contains(k)),
+                    None => None,
+                };
+                if let Some(key) = target {
+                    removed_indices.insert(key, current_index);
+                    removed_whitespace = pending_whitespace.take().or(removed_whitespace.take());
+                } else {
+                    if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
+                        writer.write_event(ws)?;
+                    }
+                    writer.write_event(Event::Empty(e.clone()))?;
+                }
+                current_index += 1;
+            }
             Ok(Event::End(ref e)) => {
                 if inside_target_data {
                     if e.name().as_ref() == b"data" {
                         inside_target_data = false;
                     }
                 } else {
-                    if let Some(ws) = pending_whitespace.take() {
+                    if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                         writer.write_event(ws)?;
                     }
                     writer.write_event(Event::End(e.clone()))?;
@@ -391,8 +1289,9 @@ pub fn remove_resx_keys(path: &Path, keys: &std::collections::HashSet<String>) -
                     let text = e.unescape()?;
                     if text.trim().is_empty() {
                         pending_whitespace = Some(Event::Text(e.clone().into_owned()));
+                        removed_whitespace = None;
                     } else {
-                        if let Some(ws) = pending_whitespace.take() {
+                        if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                             writer.write_event(ws)?;
                         }
                         writer.write_event(Event::Text(e.clone()))?;
@@ -400,14 +1299,14 @@ pub fn remove_resx_keys(path: &Path, keys: &std::collections::HashSet<String>) -
                 }
             }
             Ok(Event::Eof) => {
-                if let Some(ws) = pending_whitespace.take() {
+                if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                     writer.write_event(ws)?;
                 }
                 break;
             },
             Ok(e) => {
                  if !inside_target_data {
-                    if let Some(ws) = pending_whitespace.take() {
+                    if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                         writer.write_event(ws)?;
                     }
                     writer.write_event(e)?;
@@ -420,23 +1319,46 @@ pub fn remove_resx_keys(path: &Path, keys: &std::collections::HashSet<String>) -
 
     let mut result = writer.into_inner().into_inner();
     
-    if has_bom && !result.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        let mut new_result = vec![0xEF, 0xBB, 0xBF];
-        new_result.extend_from_slice(&result);
-        result = new_result;
-    }
+    result = keep_bom(result, has_bom);
 
-    fs::write(path, result)?;
+    write_atomically(path, &result)?;
+    if verify {
+        verify_entries(path, keys.iter().map(|key| (key.as_str(), None)))?;
+    }
 
     Ok(removed_indices)
 }
 
-pub fn remove_resx_key(path: &Path, key: &str) -> Result<usize> {
+/// Removes every `<data>` element for `key` and returns how many there were; a file can
+/// contain duplicate keys, and 0 means the key wasn't there (the file is then left untouched).
+/// `remove_resx_keys` returns the removed keys' indices instead, for restoring them later.
+/// With `dry_run` set, nothing is written to disk. With `verify` set, the file is re-read
+/// afterwards to check the key is gone.
+pub fn remove_resx_key(path: &Path, key: &str, dry_run: bool, verify: bool) -> Result<usize> {
+    let content = fs::read_to_string(path)?;
+    let (result, removed_count) = remove_key_from_content(&content, key)?;
+    if !dry_run && removed_count > 0 {
+        write_atomically(path, &result)?;
+        if verify {
+            verify_entry(path, key, None)?;
+        }
+    }
+    Ok(removed_count)
+}
+
+/// Returns what the file would look like after removing `key`, without writing it.
+pub fn preview_remove_resx_key(path: &Path, key: &str) -> Result<String> {
+    let content = fs::read_to_string(path)?;
+    let (result, _) = remove_key_from_content(&content, key)?;
+    Ok(String::from_utf8(result)?)
+}
+
+/// Returns the content without `key`'s `<data>` elements and how many were removed.
+fn remove_key_from_content(content: &str, key: &str) -> Result<(Vec<u8>, usize)> {
     // We need to remove the whole <data> block.
     // Using the reader/writer approach again is safest to identify the block boundaries.
-    let content = fs::read_to_string(path)?;
     let has_bom = content.starts_with('\u{feff}');
-    let mut reader = Reader::from_str(&content);
+    let mut reader = Reader::from_str(content);
     reader.config_mut().trim_text(false); 
 
     let mut writer = Writer::new(Cursor::new(Vec::new()));
@@ -444,9 +1366,11 @@ pub fn remove_resx_key(path: &Path, key: &str) -> Result<usize> {
 
     let mut inside_target_data = false;
     let mut pending_whitespace: Option<Event> = None;
+    // Indentation that preceded the last removed block. It is dropped if the removed block was
+    // followed by whitespace of its own, but restored if a sibling followed it directly.
+    let mut removed_whitespace: Option<Event> = None;
     
-    let mut current_index = 0;
-    let mut removed_index = 0;
+    let mut removed_count = 0;
 
     loop {
         let event = reader.read_event_into(&mut buf);
@@ -461,33 +1385,45 @@ pub fn remove_resx_key(path: &Path, key: &str) -> Result<usize> {
                             break;
                         }
                     }
-                    
-                    if is_target {
-                        removed_index = current_index;
-                    }
-                    current_index += 1;
                 }
 
                 if is_target {
+                    removed_count += 1;
                     inside_target_data = true;
-                    // Discard pending whitespace (indentation before the element)
-                    pending_whitespace = None;
+                    // Hold back the indentation before the element
+                    removed_whitespace = pending_whitespace.take().or(removed_whitespace.take());
                 } else {
                     if !inside_target_data {
-                        if let Some(ws) = pending_whitespace.take() {
+                        if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                             writer.write_event(ws)?;
                         }
                         writer.write_event(Event::Start(e.clone()))?;
                     }
                 }
             }
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" && !inside_target_data => {
+                // A self-closing <data name="..."/> has no End event, so it is dropped right here
+                let is_target = match e.try_get_attribute("name")? {
+                    Some(attr) => attr.unescape_value()? == key,
+                    None => false,
+                };
+                if is_target {
+                    removed_count += 1;
+                    removed_whitespace = pending_whitespace.take().or(removed_whitespace.take());
+                } else {
+                    if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
+                        writer.write_event(ws)?;
+                    }
+                    writer.write_event(Event::Empty(e.clone()))?;
+                }
+            }
             Ok(Event::End(ref e)) => {
                 if inside_target_data {
                     if e.name().as_ref() == b"data" {
                         inside_target_data = false;
                     }
                 } else {
-                    if let Some(ws) = pending_whitespace.take() {
+                    if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                         writer.write_event(ws)?;
                     }
                     writer.write_event(Event::End(e.clone()))?;
@@ -502,8 +1438,9 @@ pub fn remove_resx_key(path: &Path, key: &str) -> Result<usize> {
                         // Buffer whitespace
                         // We need to own the event to store it
                         pending_whitespace = Some(Event::Text(e.clone().into_owned()));
+                        removed_whitespace = None;
                     } else {
-                        if let Some(ws) = pending_whitespace.take() {
+                        if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                             writer.write_event(ws)?;
                         }
                         writer.write_event(Event::Text(e.clone()))?;
@@ -511,14 +1448,14 @@ pub fn remove_resx_key(path: &Path, key: &str) -> Result<usize> {
                 }
             }
             Ok(Event::Eof) => {
-                if let Some(ws) = pending_whitespace.take() {
+                if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                     writer.write_event(ws)?;
                 }
                 break;
             },
             Ok(e) => {
                  if !inside_target_data {
-                    if let Some(ws) = pending_whitespace.take() {
+                    if let Some(ws) = pending_whitespace.take().or_else(|| removed_whitespace.take()) {
                         writer.write_event(ws)?;
                     }
                     writer.write_event(e)?;
@@ -532,37 +1469,151 @@ pub fn remove_resx_key(path: &Path, key: &str) -> Result<usize> {
     let mut result = writer.into_inner().into_inner();
     
     // Restore BOM if it was present and lost
-    if has_bom && !result.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        let mut new_result = vec![0xEF, 0xBB, 0xBF];
-        new_result.extend_from_slice(&result);
-        result = new_result;
-    }
+    result = keep_bom(result, has_bom);
 
-    fs::write(path, result)?;
+    Ok((result, removed_count))
+}
 
-    Ok(removed_index)
+/// Length of the BOM at the start of `content`, if any. The reader skips it without counting it
+/// in `buffer_position`, so positions used to slice `content` have to be shifted by this.
+fn bom_len(content: &str) -> usize {
+    if content.starts_with('\u{feff}') { '\u{feff}'.len_utf8() } else { 0 }
 }
 
-pub fn insert_resx_key(path: &Path, key: &str, value: &str, index: usize) -> Result<()> {
-    let content = fs::read_to_string(path)?;
-    let mut reader = Reader::from_str(&content);
+/// Byte range of the `<data>` element for `key` in `content`, or `None` if there is none.
+/// Also returns the element's index among the `<data>` elements.
+fn data_element_range(content: &str, key: &str) -> Result<Option<(usize, usize, usize)>> {
+    let mut reader = Reader::from_str(content);
     reader.config_mut().trim_text(false);
-    let mut buf = Vec::new();
-    let mut count = 0;
-    let mut insert_pos = None;
-    
-    // Find position
+    let offset = bom_len(content);
+    let mut start = None;
+    let mut index = 0;
+
     loop {
-        let pos = reader.buffer_position();
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                if e.name().as_ref() == b"data" {
-                    if count == index {
-                        insert_pos = Some(pos);
-                        break;
-                    }
-                    count += 1;
-                }
+        let pos = reader.buffer_position() as usize + offset;
+        let (e, is_empty) = match reader.read_event()? {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(ref e) if e.name().as_ref() == b"data" && start.is_some() => {
+                return Ok(start.map(|start| (start, reader.buffer_position() as usize + offset, index)));
+            }
+            Event::Eof => return Ok(None),
+            _ => continue,
+        };
+        if e.name().as_ref() == b"data" && start.is_none() {
+            let is_target = match e.try_get_attribute("name")? {
+                Some(attr) => attr.unescape_value()? == key,
+                None => false,
+            };
+            if is_target && is_empty {
+                return Ok(Some((pos, reader.buffer_position() as usize + offset, index)));
+            } else if is_target {
+                start = Some(pos);
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+/// Every `<data>` element of `content` by key, as written in the file (with its attributes,
+/// comment and formatting). Like `parse_resx`, a key that occurs more than once keeps the
+/// position of its first occurrence and the element of its last; elements without a name are left out.
+fn data_elements(content: &str) -> Result<IndexMap<String, String>> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(false);
+    let offset = bom_len(content);
+    let mut elements = IndexMap::new();
+    let mut current: Option<(String, usize)> = None;
+
+    loop {
+        let pos = reader.buffer_position() as usize + offset;
+        let (key, start) = match reader.read_event()? {
+            Event::Start(e) if e.name().as_ref() == b"data" && current.is_none() => {
+                current = Some((data_attributes(&e)?.0, pos));
+                continue;
+            }
+            Event::Empty(e) if e.name().as_ref() == b"data" && current.is_none() => (data_attributes(&e)?.0, pos),
+            Event::End(e) if e.name().as_ref() == b"data" => match current.take() {
+                Some(current) => current,
+                None => continue,
+            },
+            Event::Eof => return Ok(elements),
+            _ => continue,
+        };
+        if !key.is_empty() {
+            elements.insert(key, content[start..reader.buffer_position() as usize + offset].to_string());
+        }
+    }
+}
+
+/// Moves `key` so it becomes the `new_index`-th `<data>` element, in a single write. The element
+/// is moved as it is, with its comment and formatting. Returns the index the key had before.
+pub fn move_resx_key(path: &Path, key: &str, new_index: usize) -> Result<usize> {
+    let content = fs::read_to_string(path)?;
+    let (start, end, old_index) = data_element_range(&content, key)?
+        .ok_or_else(|| ResxError::KeyNotFound(key.to_string()))?;
+    let element = &content[start..end];
+    let line_start = &content[content[..start].rfind('\n').map_or(0, |i| i + 1)..start];
+    let indent = if line_start.trim().is_empty() { line_start } else { "" };
+    let line_ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let (removed, _) = remove_key_from_content(&content, key)?;
+    let removed = String::from_utf8(removed)?;
+
+    let mut reader = Reader::from_str(&removed);
+    reader.config_mut().trim_text(false);
+    let mut count = 0;
+    let mut insert_pos = None;
+    loop {
+        let pos = reader.buffer_position() as usize + bom_len(&removed);
+        match reader.read_event()? {
+            Event::Start(ref e) | Event::Empty(ref e) if e.name().as_ref() == b"data" => {
+                if count == new_index {
+                    insert_pos = Some(pos);
+                    break;
+                }
+                count += 1;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let new_content = match insert_pos {
+        // Right before another element, whose indentation is already in place
+        Some(pos) => format!("{}{}{}{}{}", &removed[..pos], element, line_ending, indent, &removed[pos..]),
+        None => {
+            let pos = removed.rfind("</root>").ok_or_else(|| anyhow::anyhow!("No </root> element found in {}", path.display()))?;
+            format!("{}{}{}{}{}", &removed[..pos], indent, element, line_ending, &removed[pos..])
+        }
+    };
+    write_atomically(path, new_content.as_bytes())?;
+    Ok(old_index)
+}
+
+/// Inserts `key` so it becomes the `index`-th `<data>` element. Line breaks in `value` follow
+/// `options.newline` as in `update_resx_keys`. With `verify` set, the file is re-read afterwards
+/// to check the key exists with `value`.
+pub fn insert_resx_key(path: &Path, key: &str, value: &str, index: usize, verify: bool, options: &ResxWriteOptions) -> Result<usize> {
+    let content = fs::read_to_string(path)?;
+    let value = &options.normalize_value(&content, value);
+    let mut reader = Reader::from_str(&content);
+    reader.config_mut().trim_text(false);
+    let mut buf = Vec::new();
+    let mut count = 0;
+    let mut insert_pos = None;
+    
+    // Find position
+    loop {
+        let pos = reader.buffer_position() as usize + bom_len(&content);
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                if count == index {
+                    insert_pos = Some(pos);
+                    break;
+                }
+                count += 1;
             }
             Ok(Event::Eof) => break,
             Err(_) => break,
@@ -572,7 +1623,7 @@ pub fn insert_resx_key(path: &Path, key: &str, value: &str, index: usize) -> Res
     }
     
     let (start, end) = if let Some(pos) = insert_pos {
-        content.split_at(pos as usize)
+        content.split_at(pos)
     } else {
         // Append at end (before </root>)
         if let Some(idx) = content.rfind("</root>") {
@@ -593,58 +1644,446 @@ pub fn insert_resx_key(path: &Path, key: &str, value: &str, index: usize) -> Res
         &end[..len]
     };
     
+    let unit = options.indent_unit(&content);
     let (target_indent, prepend, append) = if !indent_from_start.is_empty() {
         (indent_from_start, false, true)
     } else if !indent_from_end.is_empty() {
         (indent_from_end, true, false)
     } else {
-        // Fallback: try to find indentation from other data elements or resheader
-        let fallback_indent = if let Some(_idx) = content.find("\n    <data") {
-         "    "
-    } else if let Some(_idx) = content.find("\n  <data") {
-         "  "
-    } else if let Some(_idx) = content.find("\n\t<data") {
-         "\t"
-    } else if let Some(_idx) = content.find("\n    <resheader") {
-         "    "
-    } else if let Some(_idx) = content.find("\n  <resheader") {
-         "  "
-    } else {
-         "    " // Default to 4 spaces
-    };
-        (fallback_indent, true, true)
+        (unit.as_str(), true, true)
     };
 
-    let line_ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
-    let escaped_value = minimal_escape(value);
+    let line_ending = options.line_ending(&content);
     
     let entry = format!(
-        "{0}<data name=\"{1}\" xml:space=\"preserve\">{2}{3}    <value>{4}</value>{2}{3}</data>{2}{5}",
+        "{0}{1}{2}{3}",
         if prepend { target_indent } else { "" },
-        key, 
-        line_ending, target_indent,
-        escaped_value,
+        data_element(key, value, None, line_ending, target_indent, &format!("{}{}", target_indent, unit))?,
+        line_ending,
         if append { target_indent } else { "" }
     );
 
     let new_content = format!("{}{}{}", start, entry, end);
     
-    fs::write(path, new_content)?;
+    write_atomically(path, new_content.as_bytes())?;
+    if verify {
+        verify_entry(path, key, Some(value))?;
+    }
+    Ok(new_content.len())
+}
+
+const RESX_HEADERS: [(&str, &str); 4] = [
+    ("resmimetype", "text/microsoft-resx"),
+    ("version", "2.0"),
+    ("reader", "System.Resources.ResXResourceReader, System.Windows.Forms, Version=4.0.0.0, Culture=neutral, PublicKeyToken=b77a5c561934e089"),
+    ("writer", "System.Resources.ResXResourceWriter, System.Windows.Forms, Version=4.0.0.0, Culture=neutral, PublicKeyToken=b77a5c561934e089"),
+];
+
+/// Serializes `entries` as a complete resx document with the standard `<resheader>` elements.
+pub fn write_resx_to_string(entries: &[(String, String)], options: &ResxWriteOptions) -> Result<String> {
+    let line_ending = options.line_ending("");
+    let indent = options.indent_unit("");
+    let elements = entries.iter()
+        .map(|(key, value)| data_element(key, &options.normalize_value("", value), None, line_ending, &indent, &indent.repeat(2)))
+        .collect::<Result<Vec<_>>>()?;
+    resx_document(&elements, options, "")
+}
+
+/// A complete resx document with the standard `<resheader>` elements followed by `elements`,
+/// which are written as they are. Line endings and indentation not set in `options` are
+/// taken from `like`, the content the elements come from.
+fn resx_document(elements: &[String], options: &ResxWriteOptions, like: &str) -> Result<String> {
+    let line_ending = options.line_ending(like);
+    let indent = options.indent_unit(like);
+    let newline = |depth: usize| Event::Text(BytesText::from_escaped(format!("{}{}", line_ending, indent.repeat(depth))));
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    if options.xml_declaration {
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+        writer.write_event(newline(0))?;
+    }
+    writer.write_event(Event::Start(BytesStart::new("root")))?;
+    for (name, value) in RESX_HEADERS {
+        writer.write_event(newline(1))?;
+        writer.write_event(Event::Start(BytesStart::new("resheader").with_attributes([("name", name)])))?;
+        writer.write_event(newline(2))?;
+        writer.create_element("value").write_text_content(BytesText::new(value))?;
+        writer.write_event(newline(1))?;
+        writer.write_event(Event::End(BytesEnd::new("resheader")))?;
+    }
+    for element in elements {
+        writer.write_event(newline(1))?;
+        writer.write_event(Event::Text(BytesText::from_escaped(element.as_str())))?;
+    }
+    writer.write_event(newline(0))?;
+    writer.write_event(Event::End(BytesEnd::new("root")))?;
+
+    Ok(String::from_utf8(writer.into_inner().into_inner())?)
+}
+
+/// Writes the entries of `path` into one new file per key prefix (the part before the first
+/// `prefix_separator`), named `<prefix>.resx` or `<prefix>.<lang>.resx` if the source has a language.
+/// Keys without a separator go into a file named after the source group. The `<data>` elements
+/// are copied as they are, with their comments and `type` attributes, in source order.
+/// Fails without writing anything if any of the output files already exists.
+pub fn split_resx_by_prefix(path: &Path, output_dir: &Path, prefix_separator: char, options: &ResxWriteOptions) -> Result<HashMap<String, PathBuf>> {
+    let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let (group_name, lang) = crate::scan::split_group_and_lang(file_stem);
+    let content = fs::read_to_string(path)?;
+
+    let mut by_prefix: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, element) in data_elements(&content)? {
+        let prefix = match key.split_once(prefix_separator) {
+            Some((prefix, _)) if !prefix.is_empty() => prefix.to_string(),
+            _ => group_name.clone(),
+        };
+        by_prefix.entry(prefix).or_default().push(element);
+    }
+
+    let mut outputs = HashMap::new();
+    for prefix in by_prefix.keys() {
+        if prefix.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']) {
+            return Err(anyhow::anyhow!("Prefix '{}' cannot be used as a file name", prefix));
+        }
+        let file_name = if lang == "default" { format!("{}.resx", prefix) } else { format!("{}.{}.resx", prefix, lang) };
+        let output = output_dir.join(file_name);
+        if output.exists() {
+            return Err(anyhow::anyhow!("{} already exists", output.display()));
+        }
+        outputs.insert(prefix.clone(), output);
+    }
+
+    fs::create_dir_all(output_dir)?;
+    for (prefix, elements) in by_prefix {
+        write_atomically(&outputs[&prefix], resx_document(&elements, options, &content)?.as_bytes())?;
+    }
+    Ok(outputs)
+}
+
+/// Merges every resx file directly in `input_dir` whose name matches `pattern` (`*` and `?`
+/// wildcards) into a new file at `output_path`. Returns the number of entries written.
+/// A key found in several files must have the same value in all of them. The `<data>` elements
+/// are copied as they are, in the order the files (sorted by name) and their entries come in.
+pub fn merge_resx_files(input_dir: &Path, pattern: &str, output_path: &Path, options: &ResxWriteOptions) -> Result<usize> {
+    if output_path.exists() {
+        return Err(anyhow::anyhow!("{} already exists", output_path.display()));
+    }
+
+    let mut inputs: Vec<PathBuf> = fs::read_dir(input_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().and_then(|s| s.to_str()) == Some("resx"))
+        .filter(|p| p.file_name().and_then(|s| s.to_str()).is_some_and(|n| crate::scan::matches_wildcard(n, pattern)))
+        .collect();
+    inputs.sort();
+
+    // Value and element of every key
+    let mut merged: IndexMap<String, (String, String)> = IndexMap::new();
+    let mut first_content = None;
+    for input in &inputs {
+        let content = fs::read_to_string(input)?;
+        let mut elements = data_elements(&content)?;
+        for (key, value) in parse_resx(input)?.entries {
+            match merged.get(&key) {
+                Some((existing, _)) if existing != &value => {
+                    return Err(anyhow::anyhow!("Key '{}' has conflicting values ({})", key, input.display()));
+                }
+                Some(_) => {}
+                None => {
+                    let element = elements.swap_remove(&key).unwrap_or_default();
+                    merged.insert(key, (value, element));
+                }
+            }
+        }
+        first_content.get_or_insert(content);
+    }
+
+    let elements: Vec<String> = merged.into_values().map(|(_, element)| element).collect();
+    write_atomically(output_path, resx_document(&elements, options, first_content.as_deref().unwrap_or(""))?.as_bytes())?;
+    Ok(elements.len())
+}
+
+/// Checks that `output_path` can be used as a new default file: a `.resx` file without
+/// a language suffix that doesn't exist yet.
+fn check_new_default_path(output_path: &Path) -> Result<()> {
+    if output_path.extension().and_then(|s| s.to_str()) != Some("resx") {
+        return Err(anyhow::anyhow!("{} is not a .resx file", output_path.display()));
+    }
+    let file_stem = output_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let (_, lang) = crate::scan::split_group_and_lang(file_stem);
+    if lang != "default" {
+        return Err(anyhow::anyhow!("{} has a language suffix ('{}')", output_path.display(), lang));
+    }
+    if output_path.exists() {
+        return Err(anyhow::anyhow!("{} already exists", output_path.display()));
+    }
+    Ok(())
+}
+
+/// Creates a new project directory `target_dir/project_name` with an empty `Resources.resx`,
+/// one empty `Resources.<lang>.resx` per language and a README describing the layout.
+/// Returns the created files; the project directory must not exist yet.
+pub fn scaffold_project(target_dir: &Path, project_name: &str, languages: &[String], options: &ResxWriteOptions) -> Result<Vec<PathBuf>> {
+    let is_file_name = |name: &str| !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']);
+    if !is_file_name(project_name) {
+        return Err(anyhow::anyhow!("'{}' cannot be used as a project name", project_name));
+    }
+    for (i, lang) in languages.iter().enumerate() {
+        if !is_file_name(lang) || lang.contains('.') || lang == "default" {
+            return Err(anyhow::anyhow!("'{}' is not a valid language code", lang));
+        }
+        if languages[..i].contains(lang) {
+            return Err(anyhow::anyhow!("Language '{}' is listed more than once", lang));
+        }
+    }
+    let directory = target_dir.join(project_name);
+    if directory.exists() {
+        return Err(anyhow::anyhow!("{} already exists", directory.display()));
+    }
+
+    fs::create_dir_all(&directory)?;
+    let skeleton = write_resx_to_string(&[], options)?;
+    let mut created = vec![directory.join("Resources.resx")];
+    created.extend(languages.iter().map(|lang| directory.join(format!("Resources.{}.resx", lang))));
+    for path in &created {
+        fs::write(path, &skeleton)?;
+    }
+
+    let mut readme = format!("# {}\n\n", project_name);
+    readme.push_str("Localized string resources, edited with EasyResX.\n\n");
+    readme.push_str("- `Resources.resx`: the default (neutral) language. Every key is defined here first.\n");
+    for lang in languages {
+        readme.push_str(&format!("- `Resources.{}.resx`: translations for `{}`.\n", lang, lang));
+    }
+    readme.push_str("\nA translation only needs the keys it translates; missing keys fall back to the default file.\n");
+    let readme_path = directory.join("README.md");
+    fs::write(&readme_path, readme)?;
+    created.push(readme_path);
+    Ok(created)
+}
+
+/// Recreates a default file at `output_path` as an identical copy of the translation `source`.
+/// `output_path` must be named like a default file (`<Group>.resx`, no language suffix) and must not exist.
+pub fn generate_default_from_translation(source: &Path, output_path: &Path) -> Result<()> {
+    check_new_default_path(output_path)?;
+
+    // Make sure the source is readable resx before copying it
+    parse_resx(source)?;
+    fs::copy(source, output_path)?;
+    Ok(())
+}
+
+/// `<resheader>` names that carry the language of a file.
+const CULTURE_HEADERS: [&str; 3] = ["culture", "language", "lang"];
+
+/// Copies the translation `source` to `output_path` as a neutral file: the XML declaration is
+/// set to UTF-8 and the value of any culture `<resheader>` is cleared. Everything else is kept.
+/// `output_path` must be named like a default file and must not exist.
+pub fn convert_to_neutral(source: &Path, output_path: &Path) -> Result<()> {
+    check_new_default_path(output_path)?;
+
+    let content = fs::read_to_string(source)?;
+    let has_bom = content.starts_with('\u{feff}');
+    let mut reader = Reader::from_str(&content);
+    reader.config_mut().trim_text(false);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut buf = Vec::new();
+
+    let mut in_culture_header = false;
+    let mut in_culture_value = false;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Decl(ref e)) => {
+                let version = String::from_utf8_lossy(&e.version()?).to_string();
+                let standalone = match e.standalone() {
+                    Some(standalone) => Some(String::from_utf8_lossy(&standalone?).to_string()),
+                    None => None,
+                };
+                writer.write_event(Event::Decl(BytesDecl::new(&version, Some("utf-8"), standalone.as_deref())))?;
+            }
+            Ok(Event::Start(ref e)) => {
+                if e.name().as_ref() == b"resheader" {
+                    in_culture_header = match e.try_get_attribute("name")? {
+                        Some(attr) => CULTURE_HEADERS.iter().any(|h| attr.unescape_value().is_ok_and(|v| v.eq_ignore_ascii_case(h))),
+                        None => false,
+                    };
+                } else if e.name().as_ref() == b"value" && in_culture_header {
+                    in_culture_value = true;
+                }
+                writer.write_event(Event::Start(e.clone()))?;
+            }
+            Ok(Event::Text(_)) | Ok(Event::CData(_)) if in_culture_value => {}
+            Ok(Event::End(ref e)) => {
+                if e.name().as_ref() == b"value" {
+                    in_culture_value = false;
+                } else if e.name().as_ref() == b"resheader" {
+                    in_culture_header = false;
+                }
+                writer.write_event(Event::End(e.clone()))?;
+            }
+            Ok(Event::Eof) => break,
+            Ok(e) => writer.write_event(e)?,
+            Err(e) => return Err(anyhow::anyhow!("XML Error: {:?}", e)),
+        }
+        buf.clear();
+    }
+
+    let mut result = writer.into_inner().into_inner();
+    result = keep_bom(result, has_bom);
+    write_atomically(output_path, &result)?;
+    Ok(())
+}
+
+/// Sets `keys` in `translated_file` back to their values in `default_file`, adding the ones the
+/// translation doesn't have at the end. Fails with `ResxError::KeyNotFound` before writing anything
+/// if a key isn't in the default file.
+pub fn reset_keys_to_default(default_file: &Path, translated_file: &Path, keys: &[String], options: &ResxWriteOptions) -> Result<()> {
+    let default = parse_resx(default_file)?.entries;
+    let translated = parse_resx(translated_file)?.entries;
+
+    let mut updates = HashMap::new();
+    let mut inserts = Vec::new();
+    for key in keys {
+        let value = default.get(key).ok_or_else(|| ResxError::KeyNotFound(key.clone()))?;
+        if translated.contains_key(key) {
+            updates.insert(key.clone(), value.clone());
+        } else if !inserts.iter().any(|i: &ResxInsert| &i.key == key) {
+            inserts.push(ResxInsert { key: key.clone(), value: value.clone(), comment: None, index: usize::MAX });
+        }
+    }
+
+    if !updates.is_empty() {
+        update_resx_keys(translated_file, &updates, true, false, options)?;
+    }
+    if !inserts.is_empty() {
+        insert_resx_keys(translated_file, inserts, options, false)?;
+    }
     Ok(())
 }
 
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CopyKeysReport {
+    pub copied: usize,
+    /// Requested keys the source doesn't have.
+    pub not_found: Vec<String>,
+}
+
+/// Copies `keys` from `source` to `target`, creating `target` if it doesn't exist. The `<data>` elements
+/// are copied as they are, with their comments and `type` attributes: an element the target already
+/// has is replaced, the others are appended. With `remove_from_source` set, the keys
+/// are then removed from `source`. The target is always written first, so if the second write fails
+/// the keys end up in both files rather than in neither.
+pub fn copy_keys_to_file(source: &Path, target: &Path, keys: &[String], remove_from_source: bool, options: &ResxWriteOptions) -> Result<CopyKeysReport> {
+    copy_keys_to_file_then(source, target, keys, remove_from_source, options, || Ok(()))
+}
+
+/// `copy_keys_to_file`, calling `after_target_written` between the two writes so tests can fail there.
+fn copy_keys_to_file_then(source: &Path, target: &Path, keys: &[String], remove_from_source: bool, options: &ResxWriteOptions, after_target_written: impl FnOnce() -> Result<()>) -> Result<CopyKeysReport> {
+    if source == target || (target.exists() && fs::canonicalize(source)? == fs::canonicalize(target)?) {
+        return Err(anyhow::anyhow!("Source and target are the same file"));
+    }
+    let source_content = fs::read_to_string(source)?;
+    let source_elements = data_elements(&source_content)?;
+    let mut report = CopyKeysReport::default();
+    let mut copied: IndexMap<String, String> = IndexMap::new();
+    for key in keys {
+        match source_elements.get(key) {
+            Some(element) => {
+                copied.entry(key.clone()).or_insert_with(|| element.clone());
+            }
+            None => report.not_found.push(key.clone()),
+        }
+    }
+    report.copied = copied.len();
+    if copied.is_empty() {
+        return Ok(report);
+    }
+
+    if target.exists() {
+        let mut content = fs::read_to_string(target)?;
+        let mut appended = Vec::new();
+        for (key, element) in &copied {
+            match data_element_range(&content, key)? {
+                Some((start, end, _)) => content.replace_range(start..end, element),
+                None => appended.push(element.clone()),
+            }
+        }
+        write_atomically(target, append_data_elements(&content, &appended, options)?.as_bytes())?;
+    } else {
+        let elements: Vec<String> = copied.values().cloned().collect();
+        write_atomically(target, resx_document(&elements, options, &source_content)?.as_bytes())?;
+    }
+    after_target_written()?;
+
+    if remove_from_source {
+        remove_resx_keys(source, &copied.into_keys().collect(), false)?;
+    }
+    Ok(report)
+}
+
+/// Adds `elements` to `content` as the last children of `<root>`, each on a line of its own.
+fn append_data_elements(content: &str, elements: &[String], options: &ResxWriteOptions) -> Result<String> {
+    if elements.is_empty() {
+        return Ok(content.to_string());
+    }
+    let root_end = content.rfind("</root>").ok_or_else(|| anyhow::anyhow!("The file has no </root>"))?;
+    let line_ending = options.line_ending(content);
+    let indent = options.indent_unit(content);
+
+    // Insert at the start of the line with </root>, or break the line if something precedes it
+    let line_start = content[..root_end].rfind('\n').map_or(0, |i| i + 1);
+    let (at, mut added) = if content[line_start..root_end].trim().is_empty() {
+        (line_start, String::new())
+    } else {
+        (root_end, line_ending.to_string())
+    };
+    for element in elements {
+        added.push_str(&format!("{}{}{}", indent, element, line_ending));
+    }
+    Ok(format!("{}{}{}", &content[..at], added, &content[at..]))
+}
+
 pub struct ResxInsert {
     pub key: String,
     pub value: String,
+    pub comment: Option<String>,
+    /// Position among the `<data>` elements of the file after all insertions, not in the original
+    /// file: inserting at 5 puts the new entry sixth and shifts the entries after it.
     pub index: usize,
 }
 
-pub fn insert_resx_keys(path: &Path, items: Vec<ResxInsert>) -> Result<()> {
+/// Checks that no two `items` share an index and that every index fits in a file of
+/// `key_count` entries once all items are inserted.
+pub fn check_insert_indices(items: &[ResxInsert], key_count: usize) -> Result<()> {
+    let mut seen = HashSet::new();
+    let duplicated: HashSet<usize> = items.iter().filter(|i| !seen.insert(i.index)).map(|i| i.index).collect();
+    if !duplicated.is_empty() {
+        let mut indices: Vec<usize> = items.iter().map(|i| i.index).filter(|i| duplicated.contains(i)).collect();
+        indices.sort();
+        return Err(anyhow::anyhow!("Duplicate indices in batch: {:?}", indices));
+    }
+    let final_count = key_count + items.len();
+    if let Some(item) = items.iter().find(|i| i.index >= final_count) {
+        return Err(anyhow::anyhow!("Index {} of '{}' is out of range; the file will have {} entries", item.index, item.key, final_count));
+    }
+    Ok(())
+}
+
+pub fn insert_resx_keys(path: &Path, items: Vec<ResxInsert>, options: &ResxWriteOptions, verify: bool) -> Result<usize> {
     // Sort items by index to insert efficiently during stream
+    let content = fs::read_to_string(path)?;
     let mut items = items;
     items.sort_by_key(|i| i.index);
+    for item in &mut items {
+        item.value = options.normalize_value(&content, &item.value);
+    }
+    let expected: Vec<(String, String)> = if verify {
+        items.iter().map(|i| (i.key.clone(), i.value.clone())).collect()
+    } else {
+        Vec::new()
+    };
     
-    let content = fs::read_to_string(path)?;
     let has_bom = content.starts_with('\u{feff}');
     let mut reader = Reader::from_str(&content);
     reader.config_mut().trim_text(false);
@@ -656,59 +2095,48 @@ pub fn insert_resx_keys(path: &Path, items: Vec<ResxInsert>) -> Result<()> {
     let mut output_count = 0;
     let mut item_iter = items.into_iter().peekable();
     
-    let line_ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
-    // Try to detect indentation from first data element
-    let indent = if let Some(_idx) = content.find("\n    <data") {
-         "    "
-    } else if let Some(_idx) = content.find("\n  <data") {
-         "  "
-    } else {
-         "    "
-    };
+    let line_ending = options.line_ending(&content);
+    let indent = options.indent_unit(&content);
+    let indent = indent.as_str();
 
     loop {
         let event = reader.read_event_into(&mut buf);
         match event {
-            Ok(Event::Start(ref e)) => {
-                let name = e.name();
-                if name.as_ref() == b"data" {
-                    // We are about to write an existing data element.
-                    // Before we do, check if any new items need to be inserted here.
-                    
-                    while let Some(item) = item_iter.peek() {
-                        if item.index <= output_count {
-                             let item = item_iter.next().unwrap();
-                             let escaped_value = minimal_escape(&item.value);
-                             
-                             // Strategy for INSERT (between items):
-                             // We assume we are currently at an indented position (supplied by previous Text event).
-                             // We write the element starting immediately.
-                             // We finish by writing the newline and indent that the NEXT element (or this one) needs.
-                             
-                             let entry = format!(
-                                "<data name=\"{0}\" xml:space=\"preserve\">{1}{2}{2}<value>{3}</value>{1}{2}</data>{1}{2}",
-                                item.key, line_ending, indent, escaped_value
-                             );
-                             
-                             let raw_event = Event::Text(BytesText::from_escaped(entry));
-                             writer.write_event(raw_event)?;
-                             output_count += 1;
-                        } else {
-                            break;
-                        }
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.name().as_ref() == b"data" => {
+                let is_empty = matches!(event, Ok(Event::Empty(_)));
+                // We are about to write an existing data element.
+                // Before we do, check if any new items need to be inserted here.
+                
+                while let Some(item) = item_iter.peek() {
+                    if item.index <= output_count {
+                         let item = item_iter.next().unwrap();
+                         
+                         // Strategy for INSERT (between items):
+                         // We assume we are currently at an indented position (supplied by previous Text event).
+                         // We write the element starting immediately.
+                         // We finish by writing the newline and indent that the NEXT element (or this one) needs.
+                         
+                         let entry = format!(
+                            "{0}{1}{2}",
+                            data_element(&item.key, &item.value, item.comment.as_deref(), line_ending, indent, &indent.repeat(2))?,
+                            line_ending, indent
+                         );
+                         
+                         let raw_event = Event::Text(BytesText::from_escaped(entry));
+                         writer.write_event(raw_event)?;
+                         output_count += 1;
+                    } else {
+                        break;
                     }
-                    
-                    writer.write_event(Event::Start(e.clone()))?;
-                    output_count += 1;
-                } else {
-                    writer.write_event(Event::Start(e.clone()))?;
                 }
+                
+                writer.write_event(if is_empty { Event::Empty(e.clone()) } else { Event::Start(e.clone()) })?;
+                output_count += 1;
             }
             Ok(Event::End(ref e)) => {
                 if e.name().as_ref() == b"root" {
                      // End of root. Write any remaining items (append).
-                     while let Some(item) = item_iter.next() {
-                         let escaped_value = minimal_escape(&item.value);
+                     for item in item_iter.by_ref() {
                          
                          // Strategy for APPEND (at end):
                          // We are likely at column 0 or after a newline. 
@@ -719,8 +2147,10 @@ pub fn insert_resx_keys(path: &Path, items: Vec<ResxInsert>) -> Result<()> {
                          // If we assume we are at col 0, we write {indent}<data...>{le}.
                          
                          let entry = format!(
-                            "{2}<data name=\"{0}\" xml:space=\"preserve\">{1}{2}{2}<value>{3}</value>{1}{2}</data>{1}",
-                            item.key, line_ending, indent, escaped_value
+                            "{0}{1}{2}",
+                            indent,
+                            data_element(&item.key, &item.value, item.comment.as_deref(), line_ending, indent, &indent.repeat(2))?,
+                            line_ending
                          );
                          let raw_event = Event::Text(BytesText::from_escaped(entry));
                          writer.write_event(raw_event)?;
@@ -739,69 +2169,321 @@ pub fn insert_resx_keys(path: &Path, items: Vec<ResxInsert>) -> Result<()> {
     }
     
     let mut result = writer.into_inner().into_inner();
-     if has_bom && !result.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        let mut new_result = vec![0xEF, 0xBB, 0xBF];
-        new_result.extend_from_slice(&result);
-        result = new_result;
+    result = keep_bom(result, has_bom);
+    if item_iter.next().is_some() {
+        return Err(anyhow::anyhow!("No </root> element found in {}", path.display()));
     }
     
-    fs::write(path, result)?;
-    Ok(())
+    write_atomically(path, &result)?;
+    if verify {
+        verify_entries(path, expected.iter().map(|(key, value)| (key.as_str(), Some(value.as_str()))))?;
+    }
+    Ok(result.len())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::File;
-    use std::io::Write;
-    use tempfile::tempdir;
+/// A change `apply_diff` skipped because the file didn't match what the change expected.
+#[derive(Serialize, Debug, Clone)]
+pub struct ConflictEntry {
+    pub change: ResxChange,
+    pub reason: String,
+}
 
-    #[test]
-    fn test_remove_and_restore_key() -> Result<()> {
-        let dir = tempdir()?;
-        let file_path = dir.path().join("test.resx");
-        
-        let initial_content = r###"<?xml version="1.0" encoding="utf-8"?>
-<root>
-  <data name="Key1" xml:space="preserve">
-    <value>Value1</value>
-  </data>
-  <data name="Key2" xml:space="preserve">
-    <value>Value2</value>
-  </data>
-</root>"###;
-        
-        let mut file = File::create(&file_path)?;
-        write!(file, "{}", initial_content)?;
-        
-        // Remove Key2
-        let idx = remove_resx_key(&file_path, "Key2")?;
-        assert_eq!(idx, 1);
-        
-        let content_after_remove = fs::read_to_string(&file_path)?;
-        println!("Content after remove:\n{}", content_after_remove);
-        // Expect indentation to be removed properly
-        
-        // Restore Key2
-        insert_resx_key(&file_path, "Key2", "Value2", idx)?;
-        
-        let content_after_restore = fs::read_to_string(&file_path)?;
-        println!("Content after restore:\n{}", content_after_restore);
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ApplyDiffReport {
+    pub applied: usize,
+    pub conflicts: Vec<ConflictEntry>,
+}
 
-        assert!(content_after_restore.contains("\n  <data name=\"Key2\""));
-        assert!(content_after_restore.contains("    <value>Value2</value>"));
+/// Applies `changes` to the file in a single rewrite pass. A change is skipped as a conflict if
+/// the file doesn't match what it expects (e.g. a removed or modified key has a different value,
+/// an added key already exists) or if an earlier change in the list already touched the same key.
+/// Added keys are appended at the end. With `verify` set, the file is re-read afterwards to check
+/// every applied change took effect. Line breaks in new values follow `options.newline` as in
+/// `update_resx_keys`.
+/// A modified value that was a CDATA section stays one, unless the new value contains `]]>`.
+pub fn apply_diff(path: &Path, changes: Vec<ResxChange>, options: &ResxWriteOptions, verify: bool) -> Result<ApplyDiffReport> {
+    let current = parse_resx(path)?.entries;
+    let mut report = ApplyDiffReport::default();
 
-        Ok(())
+    let mut removes = HashSet::new();
+    let mut updates: HashMap<String, String> = HashMap::new();
+    let mut renames: HashMap<String, String> = HashMap::new();
+    let mut adds: Vec<(String, String)> = Vec::new();
+    let mut touched = HashSet::new();
+
+    for change in changes {
+        let conflict = match &change {
+            _ if touched.contains(change.key()) => Some("Key is changed more than once".to_string()),
+            ResxChange::Added { key, .. } if current.contains_key(key) => Some("Key already exists".to_string()),
+            ResxChange::Removed { key, value } | ResxChange::Modified { key, old_value: value, .. } => match current.get(key) {
+                None => Some("Key not found".to_string()),
+                Some(actual) if actual != value => Some(format!("Expected value '{}' but found '{}'", value, actual)),
+                _ => None,
+            },
+            ResxChange::Renamed { old_key, .. } if !current.contains_key(old_key) => Some("Key not found".to_string()),
+            ResxChange::Renamed { new_key, .. } if current.contains_key(new_key) || touched.contains(new_key) => {
+                Some(format!("Key '{}' already exists", new_key))
+            }
+            _ => None,
+        };
+        if let Some(reason) = conflict {
+            report.conflicts.push(ConflictEntry { change, reason });
+            continue;
+        }
+
+        touched.insert(change.key().to_string());
+        match change {
+            ResxChange::Added { key, value } => adds.push((key, value)),
+            ResxChange::Removed { key, .. } => {
+                removes.insert(key);
+            }
+            ResxChange::Modified { key, new_value, .. } => {
+                updates.insert(key, new_value);
+            }
+            ResxChange::Renamed { old_key, new_key } => {
+                touched.insert(new_key.clone());
+                renames.insert(old_key, new_key);
+            }
+        }
+        report.applied += 1;
+    }
+    if report.applied == 0 {
+        return Ok(report);
     }
 
-     #[test]
-    fn test_remove_and_restore_single_key() -> Result<()> {
-        let dir = tempdir()?;
-        let file_path = dir.path().join("test_single.resx");
-        
-        // Using 4 spaces to match default fallback
-        let initial_content = r###"<?xml version="1.0" encoding="utf-8"?>
-<root>
+    let content = fs::read_to_string(path)?;
+    for value in updates.values_mut().chain(adds.iter_mut().map(|(_, value)| value)) {
+        *value = options.normalize_value(&content, value);
+    }
+    let has_bom = content.starts_with('\u{feff}');
+    let mut reader = Reader::from_str(&content);
+    reader.config_mut().trim_text(false);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut buf = Vec::new();
+
+    let line_ending = options.line_ending(&content);
+    let indent = options.indent_unit(&content);
+    let indent = indent.as_str();
+
+    // Whitespace is held back until we know whether the element after it is removed,
+    // in which case it's dropped together with the element.
+    let mut pending_text: Option<BytesText> = None;
+    let mut skip_depth = 0;
+    let mut update_value: Option<String> = None;
+    // The new value while its <value> element is read. It is written at </value>, as CDATA if
+    // the old value was, so the old content is skipped.
+    let mut updated_value: Option<String> = None;
+    let mut had_cdata = false;
+
+    let data_name = |e: &BytesStart| -> Result<Option<String>> {
+        Ok(match e.try_get_attribute("name")? {
+            Some(attr) => Some(attr.unescape_value()?.to_string()),
+            None => None,
+        })
+    };
+    let renamed = |e: &BytesStart, new_key: &str| -> Result<BytesStart<'static>> {
+        let mut start = BytesStart::new("data");
+        for attr in e.attributes() {
+            let attr = attr?;
+            if attr.key.as_ref() == b"name" {
+                start.push_attribute(("name", new_key));
+            } else {
+                start.push_attribute(attr);
+            }
+        }
+        Ok(start.into_owned())
+    };
+    // Writes a <value> into a <data> element that has none. If `closing_whitespace`, the text before
+    // the element's closing tag, ends with a line break and indentation, the value goes on its own
+    // line one level deeper, and that line break and indentation are returned.
+    let write_missing_value = |writer: &mut Writer<Cursor<Vec<u8>>>, closing_whitespace: Option<&BytesText>, value: &str| -> Result<String> {
+        let whitespace = closing_whitespace.map(|t| String::from_utf8_lossy(t).into_owned()).unwrap_or_default();
+        let closing_indent = match whitespace.rfind('\n') {
+            Some(i) if whitespace[..i].ends_with('\r') => &whitespace[i - 1..],
+            Some(i) => &whitespace[i..],
+            None => "",
+        };
+        if !closing_indent.is_empty() {
+            writer.write_event(Event::Text(BytesText::from_escaped(format!("{}{}", closing_indent, indent))))?;
+        }
+        writer.create_element("value").write_text_content(BytesText::from_escaped(minimal_escape(value)))?;
+        Ok(closing_indent.to_string())
+    };
+
+    loop {
+        let event = match reader.read_event_into(&mut buf) {
+            Ok(event) => event,
+            Err(e) => return Err(anyhow::anyhow!("XML Error: {:?}", e)),
+        };
+        if skip_depth > 0 {
+            match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                _ => {}
+            }
+            buf.clear();
+            continue;
+        }
+
+        match event {
+            Event::Text(e) if updated_value.is_none() => {
+                if let Some(text) = pending_text.take() {
+                    writer.write_event(Event::Text(text))?;
+                }
+                pending_text = Some(e.into_owned());
+            }
+            Event::Text(_) if updated_value.is_some() => {}
+            Event::CData(_) if updated_value.is_some() => had_cdata = true,
+            Event::Start(ref e) | Event::Empty(ref e) if e.name().as_ref() == b"data" => {
+                let is_empty = matches!(event, Event::Empty(_));
+                let name = data_name(e)?;
+                if name.as_ref().is_some_and(|n| removes.contains(n)) {
+                    pending_text = None;
+                    if !is_empty {
+                        skip_depth = 1;
+                    }
+                } else {
+                    let preceding_text = pending_text.take();
+                    if let Some(text) = &preceding_text {
+                        writer.write_event(Event::Text(text.clone()))?;
+                    }
+                    let start = match name.as_ref().and_then(|n| renames.get(n)) {
+                        Some(new_key) => renamed(e, new_key)?,
+                        None => e.clone().into_owned(),
+                    };
+                    update_value = name.and_then(|n| updates.get(&n).cloned());
+                    if !is_empty {
+                        writer.write_event(Event::Start(start))?;
+                    } else if let Some(value) = update_value.take() {
+                        // <data name="..."/> is expanded to hold the new value
+                        writer.write_event(Event::Start(expanded_start(&start)))?;
+                        let closing_indent = write_missing_value(&mut writer, preceding_text.as_ref(), &value)?;
+                        writer.write_event(Event::Text(BytesText::from_escaped(closing_indent)))?;
+                        writer.write_event(Event::End(BytesEnd::new("data")))?;
+                    } else {
+                        writer.write_event(Event::Empty(start))?;
+                    }
+                }
+            }
+            Event::Start(ref e) if e.name().as_ref() == b"value" && update_value.is_some() => {
+                if let Some(text) = pending_text.take() {
+                    writer.write_event(Event::Text(text))?;
+                }
+                writer.write_event(Event::Start(e.clone()))?;
+                updated_value = update_value.take();
+                had_cdata = false;
+            }
+            Event::End(ref e) if e.name().as_ref() == b"root" => {
+                if let Some(text) = pending_text.take() {
+                    writer.write_event(Event::Text(text))?;
+                }
+                for (key, value) in &adds {
+                    let entry = format!("{}{}{}", indent, data_element(key, value, None, line_ending, indent, &indent.repeat(2))?, line_ending);
+                    writer.write_event(Event::Text(BytesText::from_escaped(entry)))?;
+                }
+                writer.write_event(Event::End(e.clone()))?;
+            }
+            Event::Eof => {
+                if let Some(text) = pending_text.take() {
+                    writer.write_event(Event::Text(text))?;
+                }
+                break;
+            }
+            other => {
+                if let Event::End(ref e) = other {
+                    if e.name().as_ref() == b"value" {
+                        if let Some(value) = updated_value.take() {
+                            // A CDATA section cannot contain its own terminator, so fall back to escaped text
+                            if had_cdata && !value.contains("]]>") {
+                                writer.write_event(Event::CData(BytesCData::new(value)))?;
+                            } else {
+                                writer.write_event(Event::Text(BytesText::from_escaped(minimal_escape(&value))))?;
+                            }
+                        }
+                    } else if e.name().as_ref() == b"data" {
+                        if let Some(value) = update_value.take() {
+                            write_missing_value(&mut writer, pending_text.as_ref(), &value)?;
+                        }
+                    }
+                }
+                if let Some(text) = pending_text.take() {
+                    writer.write_event(Event::Text(text))?;
+                }
+                writer.write_event(other)?;
+            }
+        }
+        buf.clear();
+    }
+
+    let mut result = writer.into_inner().into_inner();
+    result = keep_bom(result, has_bom);
+    write_atomically(path, &result)?;
+    if verify {
+        let expected = adds.iter().map(|(key, value)| (key.as_str(), Some(value.as_str())))
+            .chain(removes.iter().map(|key| (key.as_str(), None)))
+            .chain(updates.iter().map(|(key, value)| (key.as_str(), Some(value.as_str()))))
+            .chain(renames.iter().flat_map(|(old_key, new_key)| {
+                [(old_key.as_str(), None), (new_key.as_str(), current.get(old_key).map(String::as_str))]
+            }));
+        verify_entries(path, expected)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_remove_and_restore_key() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test.resx");
+        
+        let initial_content = r###"<?xml version="1.0" encoding="utf-8"?>
+<root>
+  <data name="Key1" xml:space="preserve">
+    <value>Value1</value>
+  </data>
+  <data name="Key2" xml:space="preserve">
+    <value>Value2</value>
+  </data>
+</root>"###;
+        
+        let mut file = File::create(&file_path)?;
+        write!(file, "{}", initial_content)?;
+        
+        // Remove Key2
+        let idx = parse_resx_keys(&file_path)?.iter().position(|k| k == "Key2").unwrap();
+        assert_eq!(idx, 1);
+        assert_eq!(remove_resx_key(&file_path, "Key2", false, false)?, 1);
+        
+        let content_after_remove = fs::read_to_string(&file_path)?;
+        println!("Content after remove:\n{}", content_after_remove);
+        // Expect indentation to be removed properly
+        
+        // Restore Key2
+        insert_resx_key(&file_path, "Key2", "Value2", idx, false, &ResxWriteOptions::default())?;
+        
+        let content_after_restore = fs::read_to_string(&file_path)?;
+        println!("Content after restore:\n{}", content_after_restore);
+
+        assert!(content_after_restore.contains("\n  <data name=\"Key2\""));
+        assert!(content_after_restore.contains("    <value>Value2</value>"));
+
+        Ok(())
+    }
+
+     #[test]
+    fn test_remove_and_restore_single_key() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_single.resx");
+        
+        // Using 4 spaces to match default fallback
+        let initial_content = r###"<?xml version="1.0" encoding="utf-8"?>
+<root>
     <data name="Key1" xml:space="preserve">
         <value>Value1</value>
     </data>
@@ -811,14 +2493,15 @@ mod tests {
         write!(file, "{}", initial_content)?;
         
         // Remove Key1
-        let idx = remove_resx_key(&file_path, "Key1")?;
+        let idx = parse_resx_keys(&file_path)?.iter().position(|k| k == "Key1").unwrap();
         assert_eq!(idx, 0);
+        assert_eq!(remove_resx_key(&file_path, "Key1", false, false)?, 1);
         
         let content_after_remove = fs::read_to_string(&file_path)?;
         println!("Content after remove:\n{}", content_after_remove);
         
         // Restore Key1
-        insert_resx_key(&file_path, "Key1", "Value1", idx)?;
+        insert_resx_key(&file_path, "Key1", "Value1", idx, false, &ResxWriteOptions::default())?;
         
         let content_after_restore = fs::read_to_string(&file_path)?;
         println!("Content after restore:\n{}", content_after_restore);
@@ -829,6 +2512,93 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_remove_key_whitespace_matrix() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_ws.resx");
+
+        let cases = [
+            // Remove first of two
+            ("<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n</root>",
+             "A",
+             "<root>\n  <data name=\"B\"><value>2</value></data>\n</root>"),
+            // Remove last of two
+            ("<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n</root>",
+             "B",
+             "<root>\n  <data name=\"A\"><value>1</value></data>\n</root>"),
+            // Remove middle of three
+            ("<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n  <data name=\"C\"><value>3</value></data>\n</root>",
+             "B",
+             "<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"C\"><value>3</value></data>\n</root>"),
+            // Next block directly adjacent: it takes over the removed block's indentation
+            ("<root>\n  <data name=\"A\"><value>1</value></data><data name=\"B\"><value>2</value></data>\n</root>",
+             "A",
+             "<root>\n  <data name=\"B\"><value>2</value></data>\n</root>"),
+            // Blank line separation is kept for the remaining blocks
+            ("<root>\n  <data name=\"A\"><value>1</value></data>\n\n  <data name=\"B\"><value>2</value></data>\n</root>",
+             "A",
+             "<root>\n\n  <data name=\"B\"><value>2</value></data>\n</root>"),
+            // Only entry
+            ("<root>\n  <data name=\"A\"><value>1</value></data>\n</root>",
+             "A",
+             "<root>\n</root>"),
+        ];
+
+        for (input, key, expected) in cases {
+            fs::write(&file_path, input)?;
+            remove_resx_key(&file_path, key, false, false)?;
+            assert_eq!(fs::read_to_string(&file_path)?, expected, "removing {} from {:?}", key, input);
+
+            fs::write(&file_path, input)?;
+            remove_resx_keys(&file_path, &[key.to_string()].into_iter().collect(), false)?;
+            assert_eq!(fs::read_to_string(&file_path)?, expected, "batch removing {} from {:?}", key, input);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_key_dry_run() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_dry_run.resx");
+        let initial_content = "<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n</root>";
+        fs::write(&file_path, initial_content)?;
+
+        assert_eq!(remove_resx_key(&file_path, "B", true, false)?, 1);
+        assert_eq!(fs::read_to_string(&file_path)?, initial_content);
+        assert_eq!(
+            preview_remove_resx_key(&file_path, "B")?,
+            "<root>\n  <data name=\"A\"><value>1</value></data>\n</root>"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_self_closing_data() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_empty_data.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>a</value></data>\n  <data name=\"Empty\"/>\n  <data name=\"B\"><value>b</value></data>\n</root>")?;
+
+        assert_eq!(remove_resx_key(&file_path, "Empty", false, false)?, 1);
+        assert_eq!(fs::read_to_string(&file_path)?, "<root>\n  <data name=\"A\"><value>a</value></data>\n  <data name=\"B\"><value>b</value></data>\n</root>");
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_key_counts_duplicates() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_duplicates.resx");
+        let initial_content = "<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n  <data name=\"A\"><value>3</value></data>\n</root>";
+        fs::write(&file_path, initial_content)?;
+
+        assert_eq!(remove_resx_key(&file_path, "Missing", false, false)?, 0);
+        assert_eq!(fs::read_to_string(&file_path)?, initial_content);
+        assert_eq!(remove_resx_key(&file_path, "A", false, false)?, 2);
+        assert_eq!(parse_resx_keys(&file_path)?, vec!["B"]);
+        Ok(())
+    }
+
     #[test]
     fn test_add_key_no_extra_quote() -> Result<()> {
         let dir = tempdir()?;
@@ -842,7 +2612,7 @@ mod tests {
         write!(file, "{}", initial_content)?;
         
         // Add a new key
-        add_resx_key(&file_path, "NewKey", "")?;
+        add_resx_key(&file_path, "NewKey", "", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default())?;
         
         let content = fs::read_to_string(&file_path)?;
         println!("Content after add:\n{}", content);
@@ -850,7 +2620,951 @@ mod tests {
         // Verify no extra quote
         assert!(content.contains("<value></value>"));
         assert!(!content.contains("<value>\"</value>"));
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_key_keeps_rest_of_file() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_add_keep.resx");
+        let original = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<root>\n  <!-- header comment -->\n  <data name=\"A\" xml:space=\"preserve\">\n    <value>1</value>\n    <comment>note</comment>\n  </data>\n</root>";
+        fs::write(&file_path, original)?;
+
+        add_resx_key(&file_path, "B", "2", None, IfExistsPolicy::Error, true, &ResxWriteOptions::default())?;
+        let content = fs::read_to_string(&file_path)?;
+        assert_eq!(
+            content,
+            original.replace("</root>", "  <data name=\"B\" xml:space=\"preserve\">\n    <value>2</value>\n  </data>\n</root>")
+        );
+
+        // Adding an existing key again
+        assert!(add_resx_key(&file_path, "B", "2", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default()).is_err());
+        assert_eq!(add_resx_key(&file_path, "B", "3", None, IfExistsPolicy::Skip, false, &ResxWriteOptions::default())?, 0);
+        assert_eq!(get_value(&file_path, "B")?.as_deref(), Some("2"));
+        add_resx_key(&file_path, "B", "3", None, IfExistsPolicy::Update, true, &ResxWriteOptions::default())?;
+
+        fs::write(&file_path, "<root>")?;
+        assert!(add_resx_key(&file_path, "C", "3", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_key_escapes_name() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_add_escape.resx");
+        fs::write(&file_path, "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<root>\n</root>")?;
+
+        add_resx_key(&file_path, "Say \"Hi\" & <Bye>", "a < b", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default())?;
+
+        let content = fs::read_to_string(&file_path)?;
+        assert!(content.contains("name=\"Say &quot;Hi&quot; &amp; &lt;Bye&gt;\""));
+        assert_eq!(parse_resx(&file_path)?.entries.get("Say \"Hi\" & <Bye>").map(String::as_str), Some("a < b"));
+        assert!(add_resx_key(&file_path, "Say \"Hi\" & <Bye>", "", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_key_escapes_value() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_add_escape_value.resx");
+        fs::write(&file_path, "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<root>\n</root>")?;
+
+        add_resx_key(&file_path, "Condition", "a < b && c > d", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default())?;
+        add_resx_key(&file_path, "Quoted", "He said \"no\"", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default())?;
+
+        let entries = parse_resx(&file_path)?.entries;
+        assert_eq!(entries.get("Condition").map(String::as_str), Some("a < b && c > d"));
+        assert_eq!(entries.get("Quoted").map(String::as_str), Some("He said \"no\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_key() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_move.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>a</value><comment>c</comment></data>\n  <data name=\"B\"><value>b</value></data>\n  <data name=\"C\"/>\n</root>")?;
+
+        assert_eq!(move_resx_key(&file_path, "A", 2)?, 0);
+        assert_eq!(fs::read_to_string(&file_path)?, "<root>\n  <data name=\"B\"><value>b</value></data>\n  <data name=\"C\"/>\n  <data name=\"A\"><value>a</value><comment>c</comment></data>\n</root>");
+        assert_eq!(move_resx_key(&file_path, "C", 0)?, 1);
+        assert_eq!(parse_resx_keys(&file_path)?, vec!["C", "B", "A"]);
+        assert!(move_resx_key(&file_path, "Missing", 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_entries() {
+        let before: IndexMap<String, String> = [("A", "1"), ("B", "2"), ("C", "3")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let after: IndexMap<String, String> = [("A", "1"), ("B", "20"), ("D", "4")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+
+        let changes = diff_entries(&before, &after);
+        assert_eq!(changes, vec![
+            ResxChange::Modified { key: "B".into(), old_value: "2".into(), new_value: "20".into() },
+            ResxChange::Removed { key: "C".into(), value: "3".into() },
+            ResxChange::Added { key: "D".into(), value: "4".into() },
+        ]);
+        assert!(diff_entries(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_rename_keeps_all_entries() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_rename.resx");
+
+        let mut content = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<root>\n");
+        for i in 1..=10 {
+            content.push_str(&format!(
+                "  <data name=\"Key{0}\" xml:space=\"preserve\">\n    <value>Value{0}</value>\n  </data>\n",
+                i
+            ));
+        }
+        content.push_str("</root>");
+        fs::write(&file_path, content)?;
+
+        rename_resx_key(&file_path, "Key5", "Renamed5", false)?;
+
+        let parsed = parse_resx(&file_path)?.entries;
+        assert_eq!(parsed.len(), 10);
+        for i in 1..=10 {
+            let key = if i == 5 { "Renamed5".to_string() } else { format!("Key{}", i) };
+            assert_eq!(parsed.get(&key), Some(&format!("Value{}", i)), "missing {}", key);
+        }
+        assert!(!parsed.contains_key("Key5"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_missing_key() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_rename_missing.resx");
+        let content = "<root>\n  <data name=\"A\"><value>a</value></data>\n</root>";
+        fs::write(&file_path, content)?;
+
+        let err = rename_resx_key(&file_path, "Missing", "B", false).unwrap_err();
+        assert!(matches!(err.downcast_ref::<ResxError>(), Some(ResxError::KeyNotFound(key)) if key == "Missing"));
+        assert_eq!(fs::read_to_string(&file_path)?, content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_to_existing_key() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_rename_collision.resx");
+        let content = "<root>\n  <data name=\"A\"><value>a</value></data>\n  <data name=\"B\"><value>b</value></data>\n</root>";
+        fs::write(&file_path, content)?;
+
+        let err = rename_resx_key(&file_path, "A", "B", false).unwrap_err();
+        assert_eq!(err.to_string(), "Key 'B' already exists");
+        assert_eq!(fs::read_to_string(&file_path)?, content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_resx_keys_in_file_order() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_keys.resx");
+        fs::write(&file_path, r###"<?xml version="1.0" encoding="utf-8"?>
+<root>
+  <data name="Zeta" xml:space="preserve">
+    <value>1</value>
+  </data>
+  <data name="Alpha" xml:space="preserve">
+    <value>2</value>
+  </data>
+</root>"###)?;
+
+        assert_eq!(parse_resx_keys(&file_path)?, vec!["Zeta", "Alpha"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_resx_in_file_order() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_order.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"Zeta\"><value>1</value></data>\n  <data name=\"Alpha\"><value>2</value></data>\n  <data name=\"Zeta\"><value>3</value></data>\n  <data name=\"Mid\"><value>4</value></data>\n</root>")?;
+
+        let entries: Vec<(String, String)> = parse_resx(&file_path)?.entries.into_iter().collect();
+        assert_eq!(entries, vec![
+            ("Zeta".to_string(), "3".to_string()),
+            ("Alpha".to_string(), "2".to_string()),
+            ("Mid".to_string(), "4".to_string()),
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_value_with_comment() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_get.resx");
+        fs::write(&file_path, r###"<?xml version="1.0" encoding="utf-8"?>
+<root>
+  <data name="First" xml:space="preserve">
+    <value>One &amp; only</value>
+    <comment>Shown on the title bar</comment>
+  </data>
+  <data name="Second" xml:space="preserve">
+    <value>Two</value>
+  </data>
+</root>"###)?;
+
+        assert_eq!(get_value(&file_path, "First")?, Some("One & only".to_string()));
+        assert_eq!(
+            get_value_with_comment(&file_path, "First")?,
+            Some(("One & only".to_string(), Some("Shown on the title bar".to_string())))
+        );
+        assert_eq!(get_value_with_comment(&file_path, "Second")?, Some(("Two".to_string(), None)));
+        assert_eq!(get_value(&file_path, "Missing")?, None);
+
+        let parsed = parse_resx(&file_path)?;
+        assert_eq!(parsed.comments.len(), 1);
+        assert_eq!(parsed.comments["First"], "Shown on the title bar");
+        assert_eq!(parsed.entries["First"], "One & only");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_comments() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_comments.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\" xml:space=\"preserve\">\n    <value>a</value>\n    <comment>old</comment>\n  </data>\n  <data name=\"B\" xml:space=\"preserve\">\n    <value>b</value>\n  </data>\n</root>")?;
+
+        add_resx_key(&file_path, "C", "c", Some("Shown on <Save>"), IfExistsPolicy::Error, false, &ResxWriteOptions::default())?;
+        update_resx_comment(&file_path, "A", None)?;
+        update_resx_comment(&file_path, "B", Some("new"))?;
+        assert!(update_resx_comment(&file_path, "Missing", Some("x")).is_err());
+
+        assert_eq!(fs::read_to_string(&file_path)?, "<root>\n  <data name=\"A\" xml:space=\"preserve\">\n    <value>a</value>\n  </data>\n  <data name=\"B\" xml:space=\"preserve\">\n    <value>b</value>\n    <comment>new</comment>\n  </data>\n  <data name=\"C\" xml:space=\"preserve\">\n    <value>c</value>\n    <comment>Shown on &lt;Save&gt;</comment>\n  </data>\n</root>");
+        let entry = |key: &str, value: &str, comment: Option<&str>| ResxEntry { key: key.into(), value: value.into(), comment: comment.map(Into::into) };
+        assert_eq!(parse_resx_with_comments(&file_path)?, vec![
+            entry("A", "a", None),
+            entry("B", "b", Some("new")),
+            entry("C", "c", Some("Shown on <Save>")),
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_and_merge_by_prefix() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("Strings.fr-FR.resx");
+        let entries: Vec<(String, String)> = [("Dashboard.Title", "Tableau"), ("Settings.Header", "Réglages"), ("Dashboard.Save", "A < B"), ("Plain", "x")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let content = write_resx_to_string(&entries, &ResxWriteOptions::default())?
+            .replace("name=\"Plain\"", "name=\"Plain\" type=\"System.Int32, mscorlib\"");
+        fs::write(&file_path, content)?;
+        update_resx_comment(&file_path, "Dashboard.Title", Some("Page title"))?;
+
+        let out_dir = dir.path().join("split");
+        let outputs = split_resx_by_prefix(&file_path, &out_dir, '.', &ResxWriteOptions::default())?;
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs["Dashboard"], out_dir.join("Dashboard.fr-FR.resx"));
+        let dashboard = parse_resx(&outputs["Dashboard"])?;
+        // Source order, not sorted
+        assert_eq!(dashboard.entries.keys().collect::<Vec<_>>(), ["Dashboard.Title", "Dashboard.Save"]);
+        assert_eq!(dashboard.entries["Dashboard.Save"], "A < B");
+        assert_eq!(dashboard.comments["Dashboard.Title"], "Page title");
+        let strings = parse_resx(&outputs["Strings"])?;
+        assert_eq!(strings.entries["Plain"], "x");
+        assert_eq!(strings.types["Plain"], "System.Int32, mscorlib");
+
+        let merged_path = dir.path().join("Merged.fr-FR.resx");
+        assert_eq!(merge_resx_files(&out_dir, "*.fr-FR.resx", &merged_path, &ResxWriteOptions::default())?, 4);
+        let (original, merged) = (parse_resx(&file_path)?, parse_resx(&merged_path)?);
+        assert_eq!(merged.entries, original.entries);
+        assert_eq!(merged.comments, original.comments);
+        assert_eq!(merged.types, original.types);
+        assert_eq!(merged.entries.keys().collect::<Vec<_>>(), ["Dashboard.Title", "Dashboard.Save", "Settings.Header", "Plain"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scaffold_project() -> Result<()> {
+        let dir = tempdir()?;
+        let languages = vec!["de".to_string(), "zh-Hans".to_string()];
+        let created = scaffold_project(dir.path(), "App", &languages, &ResxWriteOptions::default())?;
+        let project = dir.path().join("App");
+        assert_eq!(created, vec![
+            project.join("Resources.resx"),
+            project.join("Resources.de.resx"),
+            project.join("Resources.zh-Hans.resx"),
+            project.join("README.md"),
+        ]);
+        assert!(parse_resx(&project.join("Resources.zh-Hans.resx"))?.entries.is_empty());
+        assert!(fs::read_to_string(project.join("README.md"))?.contains("`Resources.de.resx`"));
+
+        // Never scaffolds over an existing project
+        assert!(scaffold_project(dir.path(), "App", &[], &ResxWriteOptions::default()).is_err());
+        assert!(scaffold_project(dir.path(), "../Up", &[], &ResxWriteOptions::default()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_keys_to_file() -> Result<()> {
+        let dir = tempdir()?;
+        let source = dir.path().join("Strings.resx");
+        let target = dir.path().join("Other.resx");
+        let entries: Vec<(String, String)> = [("A", "a"), ("B", "b"), ("C", "c")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let content = write_resx_to_string(&entries, &ResxWriteOptions::default())?
+            .replace("name=\"B\"", "name=\"B\" type=\"System.Int32, mscorlib\"");
+        fs::write(&source, content)?;
+        update_resx_comment(&source, "A", Some("note"))?;
+        fs::write(&target, write_resx_to_string(&[("B".to_string(), "old".to_string())], &ResxWriteOptions::default())?)?;
+        let keys = vec!["A".to_string(), "B".to_string(), "Missing".to_string()];
+
+        // A failure between the two writes must leave every key in at least one file
+        let crashed = copy_keys_to_file_then(&source, &target, &keys, true, &ResxWriteOptions::default(), || Err(anyhow::anyhow!("simulated crash")));
+        assert!(crashed.is_err());
+        let (in_source, in_target) = (parse_resx(&source)?.entries, parse_resx(&target)?.entries);
+        for (key, value) in &entries[..2] {
+            assert!(in_source.get(key) == Some(value) || in_target.get(key) == Some(value), "{} was lost", key);
+        }
+
+        let report = copy_keys_to_file(&source, &target, &keys, true, &ResxWriteOptions::default())?;
+        assert_eq!(report.copied, 2);
+        assert_eq!(report.not_found, vec!["Missing".to_string()]);
+        assert_eq!(parse_resx_keys(&source)?, vec!["C"]);
+        let in_target = parse_resx(&target)?;
+        assert_eq!(in_target.entries.keys().collect::<Vec<_>>(), ["B", "A"]);
+        assert_eq!(in_target.entries["A"], "a");
+        assert_eq!(in_target.entries["B"], "b");
+        assert_eq!(in_target.comments["A"], "note");
+        assert_eq!(in_target.types["B"], "System.Int32, mscorlib");
+        assert!(fs::read_to_string(&target)?.ends_with("</data>\n</root>"));
+
+        let new_target = dir.path().join("New.resx");
+        copy_keys_to_file(&target, &new_target, &["A".to_string()], false, &ResxWriteOptions::default())?;
+        assert_eq!(parse_resx(&new_target)?.comments["A"], "note");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_insert_indices() {
+        let item = |key: &str, index| ResxInsert { key: key.to_string(), value: String::new(), comment: None, index };
+        assert!(check_insert_indices(&[item("A", 0), item("B", 3)], 2).is_ok());
+        let duplicate = check_insert_indices(&[item("A", 3), item("B", 1), item("C", 3)], 5).unwrap_err();
+        assert_eq!(duplicate.to_string(), "Duplicate indices in batch: [3, 3]");
+        assert!(check_insert_indices(&[item("A", 0), item("B", 4)], 2).is_err());
+    }
+
+    #[test]
+    fn test_clear_whitespace_only_values() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("Strings.resx");
+        let entries: Vec<(String, String)> = [("A", " \n  "), ("B", " b "), ("C", ""), ("D", "\t")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        fs::write(&file_path, write_resx_to_string(&entries, &ResxWriteOptions::default())?)?;
+
+        assert_eq!(clear_whitespace_only_values(&file_path, true)?, vec!["A", "D"]);
+        assert_eq!(get_value(&file_path, "A")?.as_deref(), Some(" \n  "));
+
+        assert_eq!(clear_whitespace_only_values(&file_path, false)?, vec!["A", "D"]);
+        let parsed = parse_resx(&file_path)?.entries;
+        assert_eq!(parsed["A"], "");
+        assert_eq!(parsed["B"], " b ");
+        assert_eq!(parsed["D"], "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_statistics() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("Strings.resx");
+        fs::write(&file_path, r###"<?xml version="1.0" encoding="utf-8"?>
+<root>
+  <data name="A" xml:space="preserve">
+    <value>héllo</value>
+    <comment>Greeting</comment>
+  </data>
+  <data name="B" xml:space="preserve">
+    <value></value>
+  </data>
+  <data name="Icon" type="System.Byte[], mscorlib">
+    <value>AAEC</value>
+  </data>
+</root>"###)?;
+
+        let stats = file_statistics(&file_path)?;
+        assert_eq!(stats.key_count, 3);
+        assert_eq!(stats.average_value_length, 3.0);
+        assert_eq!(stats.max_value_length, 5);
+        assert_eq!(stats.min_value_length, 0);
+        assert_eq!(stats.empty_value_count, 1);
+        assert_eq!(stats.comment_count, 1);
+        assert!(stats.has_binary_entries);
+        assert_eq!(stats.file_size_bytes, fs::metadata(&file_path)?.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_key_separators() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("Strings.resx");
+        let entries: Vec<(String, String)> = [("Button/Save", "Save"), ("Button/Cancel", "Cancel"), ("Button.Cancel", "Other"), ("Plain", "p")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        fs::write(&file_path, write_resx_to_string(&entries, &ResxWriteOptions::default())?)?;
+
+        let report = convert_key_separators(&file_path, '/', '.')?;
+        assert_eq!(report.renamed, vec![("Button/Save".to_string(), "Button.Save".to_string())]);
+        assert_eq!(report.skipped_conflicts, vec!["Button/Cancel".to_string()]);
+        assert_eq!(parse_resx_keys(&file_path)?, vec!["Button.Save", "Button/Cancel", "Button.Cancel", "Plain"]);
+        assert_eq!(get_value(&file_path, "Button.Save")?.as_deref(), Some("Save"));
+        assert_eq!(get_value(&file_path, "Button.Cancel")?.as_deref(), Some("Other"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset_keys_to_default() -> Result<()> {
+        let dir = tempdir()?;
+        let default_path = dir.path().join("Strings.resx");
+        let translated_path = dir.path().join("Strings.de.resx");
+        let entries = |pairs: &[(&str, &str)]| -> Vec<(String, String)> { pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect() };
+        fs::write(&default_path, write_resx_to_string(&entries(&[("A", "a"), ("B", "b"), ("C", "c")]), &ResxWriteOptions::default())?)?;
+        fs::write(&translated_path, write_resx_to_string(&entries(&[("A", "wrong"), ("C", "c-de")]), &ResxWriteOptions::default())?)?;
+
+        reset_keys_to_default(&default_path, &translated_path, &["A".to_string(), "B".to_string()], &ResxWriteOptions::default())?;
+        let translated = parse_resx(&translated_path)?.entries;
+        assert_eq!(translated["A"], "a");
+        assert_eq!(translated["B"], "b");
+        assert_eq!(translated["C"], "c-de");
+
+        let missing = reset_keys_to_default(&default_path, &translated_path, &["C".to_string(), "Nope".to_string()], &ResxWriteOptions::default());
+        assert_eq!(missing.unwrap_err().to_string(), "Key 'Nope' not found");
+        assert_eq!(get_value(&translated_path, "C")?.as_deref(), Some("c-de"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_writes() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_verify.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>1</value></data>\n</root>")?;
+
+        update_resx_key(&file_path, "A", "2", false, true, &ResxWriteOptions::default())?;
+        add_resx_key(&file_path, "B", "b", None, IfExistsPolicy::Error, true, &ResxWriteOptions::default())?;
+        rename_resx_key(&file_path, "B", "C", true)?;
+        remove_resx_key(&file_path, "C", false, true)?;
+
+        assert_eq!(get_value(&file_path, "A")?.as_deref(), Some("2"));
+        let err = verify_entry(&file_path, "A", Some("3")).unwrap_err();
+        assert!(matches!(err.downcast_ref::<ResxError>(), Some(ResxError::VerificationFailed { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_diff() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_apply.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n  <data name=\"C\"><value>3</value></data>\n</root>")?;
+
+        let report = apply_diff(&file_path, vec![
+            ResxChange::Modified { key: "A".into(), old_value: "1".into(), new_value: "10".into() },
+            ResxChange::Removed { key: "B".into(), value: "2".into() },
+            ResxChange::Renamed { old_key: "C".into(), new_key: "See".into() },
+            ResxChange::Added { key: "D".into(), value: "4".into() },
+            // Conflicts: stale value, existing key, key already changed above
+            ResxChange::Removed { key: "A".into(), value: "1".into() },
+            ResxChange::Added { key: "C".into(), value: "x".into() },
+            ResxChange::Modified { key: "Z".into(), old_value: "".into(), new_value: "z".into() },
+        ], &ResxWriteOptions::default(), true)?;
+        assert_eq!(report.applied, 4);
+        assert_eq!(report.conflicts.len(), 3);
+
+        assert_eq!(
+            fs::read_to_string(&file_path)?,
+            "<root>\n  <data name=\"A\"><value>10</value></data>\n  <data name=\"See\"><value>3</value></data>\n  <data name=\"D\" xml:space=\"preserve\">\n    <value>4</value>\n  </data>\n</root>"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_diff_keeps_cdata() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_apply_cdata.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value><![CDATA[<b>a</b>]]></value></data>\n  <data name=\"B\"><value><![CDATA[<b>b</b>]]></value></data>\n</root>")?;
+
+        apply_diff(&file_path, vec![
+            ResxChange::Modified { key: "A".into(), old_value: "<b>a</b>".into(), new_value: "<i>a</i>".into() },
+            // A value with the CDATA terminator is written as escaped text
+            ResxChange::Modified { key: "B".into(), old_value: "<b>b</b>".into(), new_value: "]]>".into() },
+        ], &ResxWriteOptions::default(), true)?;
+        assert_eq!(
+            fs::read_to_string(&file_path)?,
+            "<root>\n  <data name=\"A\"><value><![CDATA[<i>a</i>]]></value></data>\n  <data name=\"B\"><value>]]&gt;</value></data>\n</root>"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_diff_to_data_without_value() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_apply_empty.resx");
+        fs::write(&file_path, "<root>\r\n  <data name=\"A\" />\r\n  <data name=\"B\">\r\n    <comment>note</comment>\r\n  </data>\r\n</root>")?;
+
+        let report = apply_diff(&file_path, vec![
+            ResxChange::Modified { key: "A".into(), old_value: "".into(), new_value: "1".into() },
+            ResxChange::Modified { key: "B".into(), old_value: "".into(), new_value: "2".into() },
+        ], &ResxWriteOptions::default(), true)?;
+        assert_eq!(report.applied, 2);
+        assert_eq!(
+            fs::read_to_string(&file_path)?,
+            "<root>\r\n  <data name=\"A\">\r\n    <value>1</value>\r\n  </data>\r\n  <data name=\"B\">\r\n    <comment>note</comment>\r\n    <value>2</value>\r\n  </data>\r\n</root>"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_iterator_stops_early() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_iter.resx");
+        // Everything after the second entry is malformed, so reading it would fail
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>1 &amp; 2</value></data>\n  <data name=\"B\"><value><![CDATA[<b>]]></value></data>\n  <data name=\"C\"><value>3</oops></data>\n</root>")?;
+
+        let first_two: Vec<(String, String)> = ResxKeyIterator::open(&file_path)?.take(2).collect::<std::result::Result<_, _>>()?;
+        assert_eq!(first_two, vec![("A".to_string(), "1 & 2".to_string()), ("B".to_string(), "<b>".to_string())]);
+        assert!(key_exists(&file_path, "B")?);
+        assert_eq!(count_keys_streaming(&file_path, Some(2))?, 2);
+
+        let mut iter = ResxKeyIterator::open(&file_path)?.skip(2);
+        assert!(matches!(iter.next(), Some(Err(ResxError::Xml { .. }))));
+        assert!(iter.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_self_closing_data() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_self_closing.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>a</value></data>\n  <data name=\"X\"/>\n  <data name=\"B\"><value>b</value></data>\n</root>")?;
+
+        let entries: Vec<(String, String)> = ResxKeyIterator::open(&file_path)?.collect::<std::result::Result<_, _>>()?;
+        assert_eq!(entries, vec![("A".into(), "a".into()), ("X".into(), "".into()), ("B".into(), "b".into())]);
+        assert!(key_exists(&file_path, "X")?);
+        assert_eq!(get_value(&file_path, "X")?, Some(String::new()));
+        assert_eq!(get_value(&file_path, "B")?, Some("b".to_string()));
+        assert_eq!(parse_resx(&file_path)?.entries.get_index_of("X"), Some(1));
+        assert_eq!(file_statistics(&file_path)?.key_count, 3);
+
+        // The existing entry is found, so no second "X" is added
+        assert!(add_resx_key(&file_path, "X", "x", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default()).is_err());
+        add_resx_key(&file_path, "X", "x", None, IfExistsPolicy::Skip, false, &ResxWriteOptions::default())?;
+        assert_eq!(parse_resx_keys(&file_path)?, ["A", "X", "B"]);
+
+        rename_resx_key(&file_path, "X", "Y", true)?;
+        assert_eq!(parse_resx_keys(&file_path)?, ["A", "Y", "B"]);
+        assert!(fs::read_to_string(&file_path)?.contains("<data name=\"Y\"/>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_counts_self_closing_data() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_insert_self_closing.resx");
+        let content = "<root>\n  <data name=\"A\"><value>a</value></data>\n  <data name=\"B\"/>\n  <data name=\"C\"><value>c</value></data>\n</root>";
+
+        fs::write(&file_path, content)?;
+        insert_resx_key(&file_path, "N", "n", 2, false, &ResxWriteOptions::default())?;
+        assert_eq!(parse_resx_keys(&file_path)?, ["A", "B", "N", "C"]);
+
+        fs::write(&file_path, content)?;
+        let item = |key: &str, index| ResxInsert { key: key.to_string(), value: String::new(), comment: None, index };
+        insert_resx_keys(&file_path, vec![item("N", 2), item("M", 4)], &ResxWriteOptions::default(), true)?;
+        assert_eq!(parse_resx_keys(&file_path)?, ["A", "B", "N", "C", "M"]);
+        assert!(fs::read_to_string(&file_path)?.contains("<data name=\"B\"/>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_with_assembly_elements() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_assembly.resx");
+        fs::write(&file_path, r#"<root>
+  <assembly alias="System.Windows.Forms" name="System.Windows.Forms, Version=4.0.0.0, Culture=neutral" />
+  <data name="A"><value>a</value></data>
+  <assembly alias="System.Drawing" name="System.Drawing, Version=4.0.0.0"></assembly>
+  <data name="B"><value>b</value></data>
+</root>"#)?;
+
+        let entries = parse_resx(&file_path)?.entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["A"], "a");
+        assert_eq!(entries["B"], "b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_warns_on_empty_key() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_empty_key.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"\"><value>lost</value></data>\n  <data name=\"A\"><value>1</value></data>\n</root>")?;
+
+        let parsed = parse_resx(&file_path)?;
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries["A"], "1");
+        assert!(matches!(parsed.warnings.as_slice(), [ParseWarning::EmptyKey { .. }]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_to_neutral() -> Result<()> {
+        let dir = tempdir()?;
+        let source = dir.path().join("Messages.en-US.resx");
+        fs::write(&source, "<?xml version=\"1.0\" encoding=\"windows-1252\"?>\n<root>\n  <resheader name=\"culture\"><value>en-US</value></resheader>\n  <resheader name=\"version\"><value>2.0</value></resheader>\n  <data name=\"A\"><value>1</value></data>\n</root>")?;
+
+        assert!(convert_to_neutral(&source, &dir.path().join("Messages.de.resx")).is_err());
+
+        let output = dir.path().join("Messages.resx");
+        convert_to_neutral(&source, &output)?;
+        assert_eq!(
+            fs::read_to_string(&output)?,
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<root>\n  <resheader name=\"culture\"><value></value></resheader>\n  <resheader name=\"version\"><value>2.0</value></resheader>\n  <data name=\"A\"><value>1</value></data>\n</root>"
+        );
+        assert!(source.exists());
+        assert!(convert_to_neutral(&source, &output).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_keys_reports_not_found() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_batch.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n</root>")?;
+
+        let updates: HashMap<String, String> = [("B", "20"), ("Z", "26"), ("A", "10")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let report = update_resx_keys(&file_path, &updates, false, true, &ResxWriteOptions::default())?;
+        assert_eq!(report.updated, vec!["A", "B"]);
+        assert_eq!(report.not_found, vec!["Z"]);
+
+        let entries = parse_resx(&file_path)?.entries;
+        assert_eq!(entries["A"], "10");
+        assert_eq!(entries["B"], "20");
+        assert!(!entries.contains_key("Z"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_keys_without_value() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_no_value.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\">\n    <comment>note</comment>\n  </data>\n  <data name=\"B\" />\n  <data name=\"C\"></data>\n</root>")?;
+
+        let updates: HashMap<String, String> = [("A", "1"), ("B", "2"), ("C", "3")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let report = update_resx_keys(&file_path, &updates, false, true, &ResxWriteOptions::default())?;
+        assert_eq!(report.updated, vec!["A", "B", "C"]);
+        assert_eq!(
+            fs::read_to_string(&file_path)?,
+            "<root>\n  <data name=\"A\">\n    <comment>note</comment>\n    <value>1</value>\n  </data>\n  <data name=\"B\">\n    <value>2</value>\n  </data>\n  <data name=\"C\"><value>3</value></data>\n</root>"
+        );
+
+        // Updating an existing key through add_resx_key works on a self-closing element too
+        fs::write(&file_path, "<root>\n  <data name=\"D\" />\n</root>")?;
+        add_resx_key(&file_path, "D", "4", None, IfExistsPolicy::Update, true, &ResxWriteOptions::default())?;
+        assert_eq!(parse_resx(&file_path)?.entries["D"], "4");
+        Ok(())
+    }
+
+    #[test]
+    fn test_writes_leave_no_temp_files() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_atomic.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n</root>")?;
+
+        update_resx_key(&file_path, "A", "10", true, false, &ResxWriteOptions::default())?;
+        rename_resx_key(&file_path, "A", "C", false)?;
+        remove_resx_key(&file_path, "B", false, false)?;
+        add_resx_key(&file_path, "D", "4", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default())?;
+        apply_diff(&file_path, vec![ResxChange::Modified { key: "D".into(), old_value: "4".into(), new_value: "5".into() }], &ResxWriteOptions::default(), false)?;
+
+        let names: Vec<_> = fs::read_dir(dir.path())?.map(|e| e.map(|e| e.file_name())).collect::<Result<_, _>>()?;
+        assert_eq!(names, vec!["test_atomic.resx"]);
+        assert_eq!(parse_resx_keys(&file_path)?, vec!["C", "D"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_writes_keep_bom() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_bom.resx");
+        fs::write(&file_path, "\u{feff}<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<root>\n  <data name=\"A\"><value>1</value></data>\n  <data name=\"B\"><value>2</value></data>\n</root>")?;
+        assert!(has_bom(&file_path));
+
+        update_resx_key(&file_path, "A", "10", true, false, &ResxWriteOptions::default())?;
+        assert!(fs::read(&file_path)?.starts_with(&[0xEF, 0xBB, 0xBF]));
+        rename_resx_key(&file_path, "A", "C", false)?;
+        assert!(has_bom(&file_path));
+        move_resx_key(&file_path, "C", 1)?;
+        assert!(has_bom(&file_path));
+        insert_resx_key(&file_path, "D", "4", 0, false, &ResxWriteOptions::default())?;
+        assert!(has_bom(&file_path));
+        add_resx_key(&file_path, "E", "5", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default())?;
+        assert!(has_bom(&file_path));
+        remove_resx_key(&file_path, "B", false, false)?;
+        assert!(has_bom(&file_path));
+
+        assert_eq!(parse_resx_keys(&file_path)?, vec!["D", "C", "E"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_missing_key_fails() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_update_missing.resx");
+        let initial_content = "<root>\n  <data name=\"A\"><value>1</value></data>\n</root>";
+        fs::write(&file_path, initial_content)?;
+
+        let err = update_resx_key(&file_path, "Missing", "x", true, false, &ResxWriteOptions::default()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<ResxError>(), Some(ResxError::KeyNotFound(key)) if key == "Missing"));
+        assert_eq!(err.to_string(), "Key 'Missing' not found");
+        assert_eq!(fs::read_to_string(&file_path)?, initial_content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_default_from_translation() -> Result<()> {
+        let dir = tempdir()?;
+        let source = dir.path().join("Messages.en-US.resx");
+        fs::write(&source, "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<root>\n  <data name=\"A\"><value>1</value></data>\n</root>")?;
+
+        assert!(generate_default_from_translation(&source, &dir.path().join("Messages.fr.resx")).is_err());
+
+        let output = dir.path().join("Messages.resx");
+        generate_default_from_translation(&source, &output)?;
+        assert_eq!(fs::read(&output)?, fs::read(&source)?);
+        assert!(generate_default_from_translation(&source, &output).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_encoding() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_encoding.resx");
+        let decl = "<?xml version=\"1.0\" encoding=\"utf-8\"?><root/>";
+
+        fs::write(&file_path, decl)?;
+        assert_eq!(detect_encoding(&file_path)?, FileEncoding::Utf8);
+
+        fs::write(&file_path, [&[0xEF, 0xBB, 0xBF][..], decl.as_bytes()].concat())?;
+        assert_eq!(detect_encoding(&file_path)?, FileEncoding::Utf8Bom);
+
+        let utf16le: Vec<u8> = [0xFF, 0xFE].into_iter().chain(decl.encode_utf16().flat_map(|u| u.to_le_bytes())).collect();
+        fs::write(&file_path, utf16le)?;
+        assert_eq!(detect_encoding(&file_path)?, FileEncoding::Utf16Le);
+
+        let utf16be: Vec<u8> = decl.encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+        fs::write(&file_path, utf16be)?;
+        assert_eq!(detect_encoding(&file_path)?, FileEncoding::Utf16Be);
+
+        fs::write(&file_path, "<?xml version=\"1.0\" encoding=\"windows-1252\"?><root/>")?;
+        assert_eq!(detect_encoding(&file_path)?, FileEncoding::Unknown);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_writes_keep_header_comment() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_header.resx");
+        let header = r###"<?xml version="1.0" encoding="utf-8"?>
+<root>
+  <!-- 
+    Microsoft ResX Schema 
+    
+    Version 2.0
+    
+    Example:
+
+    <data name="Name1"><value>this is my long string</value><comment>this is a comment</comment></data>
+    <data name="Color1" type="System.Drawing.Color, System.Drawing">Blue</data>
+
+    mimetype: application/x-microsoft.net.object.binary.base64 & friends
+    -->
+  <xsd:schema id="root" xmlns="" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:msdata="urn:schemas-microsoft-com:xml-msdata">
+    <xsd:import namespace="http://www.w3.org/XML/1998/namespace" />
+  </xsd:schema>
+  <resheader name="resmimetype">
+    <value>text/microsoft-resx</value>
+  </resheader>
+  <resheader name="version">
+    <value>2.0</value>
+  </resheader>
+"###;
+        fs::write(&file_path, format!("{}  <data name=\"A\" xml:space=\"preserve\">\n    <value>a</value>\n  </data>\n  <data name=\"B\" xml:space=\"preserve\">\n    <value>b</value>\n  </data>\n</root>", header))?;
+        let options = ResxWriteOptions::default();
+        let assert_header = || -> Result<()> {
+            assert!(fs::read_to_string(&file_path)?.starts_with(header));
+            Ok(())
+        };
+
+        update_resx_key(&file_path, "A", "a2", true, false, &ResxWriteOptions::default())?;
+        assert_header()?;
+        rename_resx_key(&file_path, "A", "C", false)?;
+        assert_header()?;
+        add_resx_key(&file_path, "D", "d", Some("note"), IfExistsPolicy::Error, false, &options)?;
+        assert_header()?;
+        insert_resx_key(&file_path, "E", "e", 0, false, &options)?;
+        assert_header()?;
+        move_resx_key(&file_path, "E", 2)?;
+        assert_header()?;
+        update_resx_comment(&file_path, "B", Some("b note"))?;
+        assert_header()?;
+        remove_resx_keys(&file_path, &HashSet::from(["B".to_string()]), true)?;
+        assert_header()?;
+        remove_resx_key(&file_path, "D", false, false)?;
+        assert_header()?;
+        apply_diff(&file_path, vec![ResxChange::Added { key: "F".into(), value: "f".into() }], &options, false)?;
+        assert_header()?;
+
+        assert_eq!(parse_resx_keys(&file_path)?, vec!["C", "E", "F"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_keeps_cdata_pi_and_doctype() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_events.resx");
+        let original = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE root [<!ENTITY app \"EasyResX\">]>\n<?xml-stylesheet type=\"text/xsl\" href=\"resx.xsl\"?>\n<root>\n  <data name=\"Html\"><value><![CDATA[<b>Bold</b>]]></value></data>\n  <?editor hint=\"keep\"?>\n  <data name=\"Plain\"><value>old</value></data>\n</root>";
+        fs::write(&file_path, original)?;
+
+        update_resx_key(&file_path, "Plain", "new", true, false, &ResxWriteOptions::default())?;
+        assert_eq!(fs::read_to_string(&file_path)?, original.replace("<value>old</value>", "<value>new</value>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_writes_keep_root_namespaces() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_namespaces.resx");
+        let root = "<root xmlns=\"\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xmlns:msdata=\"urn:schemas-microsoft-com:xml-msdata\">";
+        fs::write(&file_path, format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{}\n  <data name=\"A\" xml:space=\"preserve\"><value>a</value></data>\n  <data name=\"B\" xml:space=\"preserve\"><value>b</value></data>\n</root>", root))?;
+
+        update_resx_key(&file_path, "A", "a2", false, false, &ResxWriteOptions::default())?;
+        assert!(fs::read_to_string(&file_path)?.contains(root));
+        rename_resx_key(&file_path, "A", "C", false)?;
+        assert!(fs::read_to_string(&file_path)?.contains(root));
+        remove_resx_key(&file_path, "B", false, false)?;
+        assert!(fs::read_to_string(&file_path)?.contains(root));
+        add_resx_key(&file_path, "D", "d", None, IfExistsPolicy::Error, false, &ResxWriteOptions::default())?;
+        assert!(fs::read_to_string(&file_path)?.contains(root));
+
+        let entries = parse_resx(&file_path)?.entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["C"], "a2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_options() -> Result<()> {
+        let options = ResxWriteOptions { indent: IndentStyle::Tabs, newline: NewlineStyle::Crlf, xml_declaration: false, ..Default::default() };
+        let content = write_resx_to_string(&[("A".to_string(), "a".to_string())], &options)?;
+        assert!(content.starts_with("<root>\r\n\t<resheader name=\"resmimetype\">\r\n\t\t<value>"));
+        assert!(content.ends_with("\t<data name=\"A\" xml:space=\"preserve\">\r\n\t\t<value>a</value>\r\n\t</data>\r\n</root>"));
+
+        // The file's own indentation wins over the configured one
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_write_options.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>a</value></data>\n</root>")?;
+        add_resx_key(&file_path, "B", "b", None, IfExistsPolicy::Error, false, &options)?;
+        assert!(fs::read_to_string(&file_path)?.ends_with("\n  <data name=\"B\" xml:space=\"preserve\">\r\n    <value>b</value>\r\n  </data>\r\n</root>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_keeps_crlf() -> Result<()> {
+        assert_eq!(detect_line_endings("a\nb"), LineEnding::Lf);
+        assert_eq!(detect_line_endings("a\r\nb\r\n"), LineEnding::Crlf);
+        assert_eq!(detect_line_endings("a\r\nb\n"), LineEnding::Mixed);
+
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_crlf.resx");
+        fs::write(&file_path, "<root>\r\n  <data name=\"A\"><value>a</value></data>\r\n</root>")?;
+        update_resx_key(&file_path, "A", "line 1\nline 2", false, false, &ResxWriteOptions::default())?;
+        assert_eq!(fs::read_to_string(&file_path)?, "<root>\r\n  <data name=\"A\"><value>line 1\r\nline 2</value></data>\r\n</root>");
+
+        // A configured newline style wins over the one the file uses
+        let lf = ResxWriteOptions { newline: NewlineStyle::Lf, ..Default::default() };
+        update_resx_key(&file_path, "A", "line 1\r\nline 2", false, true, &lf)?;
+        assert_eq!(fs::read_to_string(&file_path)?, "<root>\r\n  <data name=\"A\"><value>line 1\nline 2</value></data>\r\n</root>");
+        let crlf = ResxWriteOptions { newline: NewlineStyle::Crlf, ..Default::default() };
+        fs::write(&file_path, "<root>\n  <data name=\"A\"><value>a</value></data>\n</root>")?;
+        update_resx_key(&file_path, "A", "line 1\nline 2", false, false, &crlf)?;
+        assert_eq!(fs::read_to_string(&file_path)?, "<root>\n  <data name=\"A\"><value>line 1\r\nline 2</value></data>\n</root>");
+
+        // Added keys and applied changes get the same line breaks as updates
+        fs::write(&file_path, "<root>\r\n  <data name=\"A\"><value>a</value></data>\r\n</root>")?;
+        add_resx_key(&file_path, "B", "b 1\nb 2", None, IfExistsPolicy::Error, true, &crlf)?;
+        insert_resx_key(&file_path, "C", "c 1\nc 2", 0, true, &crlf)?;
+        apply_diff(&file_path, vec![
+            ResxChange::Modified { key: "A".into(), old_value: "a".into(), new_value: "a 1\na 2".into() },
+            ResxChange::Added { key: "D".into(), value: "d 1\nd 2".into() },
+        ], &crlf, true)?;
+        let content = fs::read_to_string(&file_path)?;
+        for value in ["a 1\r\na 2", "b 1\r\nb 2", "c 1\r\nc 2", "d 1\r\nd 2"] {
+            assert!(content.contains(value), "{} not in {}", value, content);
+        }
+        assert!(!content.replace("\r\n", "").contains('\n'));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdata_value_round_trip() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_cdata.resx");
+
+        let initial_content = r###"<?xml version="1.0" encoding="utf-8"?>
+<root>
+  <data name="Html" xml:space="preserve">
+    <value><![CDATA[<b>Bold</b>]]></value>
+  </data>
+  <data name="Plain" xml:space="preserve">
+    <value>a &amp; b</value>
+  </data>
+</root>"###;
+        fs::write(&file_path, initial_content)?;
+
+        let parsed = parse_resx(&file_path)?.entries;
+        assert_eq!(parsed["Html"], "<b>Bold</b>");
+        assert_eq!(parsed["Plain"], "a & b");
+
+        update_resx_key(&file_path, "Html", "<i>Italic</i>", true, false, &ResxWriteOptions::default())?;
+        let content = fs::read_to_string(&file_path)?;
+        assert!(content.contains("<value><![CDATA[<i>Italic</i>]]></value>"));
+        assert_eq!(parse_resx(&file_path)?.entries["Html"], "<i>Italic</i>");
+
+        update_resx_key(&file_path, "Html", "<u>Under</u>", false, false, &ResxWriteOptions::default())?;
+        let content = fs::read_to_string(&file_path)?;
+        assert!(content.contains("<value>&lt;u&gt;Under&lt;/u&gt;</value>"));
+        assert_eq!(parse_resx(&file_path)?.entries["Html"], "<u>Under</u>");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdata_mixed_and_terminator() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test_cdata_mixed.resx");
+        fs::write(&file_path, "<root>\n  <data name=\"Mixed\"><value>a &amp; <![CDATA[<b>b</b>]]> c</value></data>\n</root>")?;
+
+        assert_eq!(parse_resx(&file_path)?.entries["Mixed"], "a & <b>b</b> c");
+
+        // "]]>" can't go inside a CDATA section, so it is written as escaped text instead
+        update_resx_key(&file_path, "Mixed", "x]]>y", true, false, &ResxWriteOptions::default())?;
+        let content = fs::read_to_string(&file_path)?;
+        assert!(content.contains("<value>x]]&gt;y</value>"));
+        assert_eq!(parse_resx(&file_path)?.entries["Mixed"], "x]]>y");
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,686 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use ignore::gitignore::Gitignore;
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+use walkdir::{DirEntry, WalkDir};
+use crate::{ResxFile, ResxGroup};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// How deep to descend below the scanned directory; `None` means unlimited.
+    pub max_depth: Option<usize>,
+    /// Directory names to skip entirely, e.g. "bin" or "obj".
+    pub exclude_dirs: Vec<String>,
+    /// Order of the returned groups.
+    pub sort_by: GroupSortOrder,
+    /// Skip whatever the `.gitignore` files in the scanned tree, and in its parent directories up
+    /// to the repository root, ignore (e.g. `bin/` and `obj/`).
+    pub respect_gitignore: bool,
+}
+
+/// Orders groups by name, by directory (then name), or with the largest or most recently
+/// modified groups first. Ties are broken by name.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum GroupSortOrder {
+    #[default]
+    Name,
+    Directory,
+    /// Keys in the default file (or the first file if there is none). Reads the files while sorting.
+    KeyCount,
+    FileCount,
+    /// Modification time of the most recently modified file of the group.
+    LastModified,
+}
+
+/// Resolves the directory to scan to its canonical form (no symlinks or `..` components),
+/// so equivalent paths always produce the same group directories. Fails if `path` is not
+/// an existing directory, rather than letting the scan come back empty.
+pub fn resolve_root(path: &str) -> Result<PathBuf, String> {
+    let root = Path::new(path);
+    if !root.exists() {
+        return Err(format!("Directory '{}' does not exist", path));
+    }
+    if !root.is_dir() {
+        return Err(format!("'{}' is not a directory", path));
+    }
+    dunce::canonicalize(path).map_err(|e| format!("Cannot scan '{}': {}", path, e))
+}
+
+/// Splits a resx file stem like "Messages.fr-FR" into its group name and language.
+/// Files without a language suffix belong to the "default" language.
+pub fn split_group_and_lang(file_stem: &str) -> (String, String) {
+    // Heuristic: Split by dot. Last part is lang if short, else default.
+    let parts: Vec<&str> = file_stem.split('.').collect();
+    if parts.len() > 1 {
+        let potential_lang = parts.last().unwrap();
+        // Valid lang codes are usually 2-3 chars or 5 chars (en, en-US)
+        // Some are longer "zh-Hans", "az-Latn-AZ"
+        if potential_lang.len() <= 10 && potential_lang.chars().next().unwrap_or(' ').is_ascii_alphabetic() {
+            return (parts[..parts.len() - 1].join("."), potential_lang.to_string());
+        }
+    }
+    (file_stem.to_string(), "default".to_string())
+}
+
+/// Returns a language code with BCP 47 canonical casing: language lowercase, script
+/// title case and region uppercase, e.g. "ZH-HANS" becomes "zh-Hans" and "en-us" becomes "en-US".
+pub fn canonical_lang_code(lang: &str) -> String {
+    lang.split('-').enumerate().map(|(i, part)| {
+        if i == 0 {
+            part.to_ascii_lowercase()
+        } else if part.len() == 4 && part.chars().all(|c| c.is_ascii_alphabetic()) {
+            let (first, rest) = part.split_at(1);
+            first.to_ascii_uppercase() + &rest.to_ascii_lowercase()
+        } else if part.len() == 2 || (part.len() == 3 && part.chars().all(|c| c.is_ascii_digit())) {
+            part.to_ascii_uppercase()
+        } else {
+            part.to_ascii_lowercase()
+        }
+    }).collect::<Vec<_>>().join("-")
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RenameOperation {
+    pub old_path: String,
+    pub new_path: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SkippedRename {
+    pub old_path: String,
+    pub new_path: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct LanguageRenamePlan {
+    pub renames: Vec<RenameOperation>,
+    /// Renames left out because another file already has (or would get) the new name.
+    pub skipped: Vec<SkippedRename>,
+}
+
+/// Lists the renames needed to give the files of group `group_name` in `directory`
+/// canonically cased language codes. Nothing is renamed.
+/// A rename whose new name is taken by another file is skipped rather than overwriting it.
+/// Names are compared exactly, so a case-only rename on a case-insensitive file system,
+/// where the old and new name are the same file, is not a collision.
+pub fn plan_language_renames(directory: &Path, group_name: &str) -> std::io::Result<LanguageRenamePlan> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        paths.push(entry?.path());
+    }
+    paths.sort();
+    let mut taken: HashSet<String> = paths.iter()
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect();
+
+    let mut plan = LanguageRenamePlan::default();
+    for path in paths {
+        if path.extension().and_then(|s| s.to_str()) != Some("resx") {
+            continue;
+        }
+        let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let (name, lang) = split_group_and_lang(file_stem);
+        if name != group_name || lang == "default" {
+            continue;
+        }
+        let canonical = canonical_lang_code(&lang);
+        if canonical == lang {
+            continue;
+        }
+        let new_name = format!("{}.{}.resx", name, canonical);
+        let new_path = path.with_file_name(&new_name);
+        let (old_path, new_path) = (path.to_string_lossy().to_string(), new_path.to_string_lossy().to_string());
+        if taken.insert(new_name) {
+            plan.renames.push(RenameOperation { old_path, new_path });
+        } else {
+            plan.skipped.push(SkippedRename { old_path, new_path, reason: "A file with the new name already exists".to_string() });
+        }
+    }
+    Ok(plan)
+}
+
+/// Matches a file name against a pattern where `*` matches any run of characters and `?` one character.
+pub fn matches_wildcard(name: &str, pattern: &str) -> bool {
+    let name: Vec<char> = name.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut n, mut p) = (0, 0);
+    // Position after the last `*` and the name position it was tried at, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            n += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Sorts files in a group: default first, then alphabetical.
+pub fn sort_group_files(files: &mut [ResxFile]) {
+    files.sort_by(|a, b| {
+        if a.lang == "default" { Ordering::Less }
+        else if b.lang == "default" { Ordering::Greater }
+        else { a.lang.cmp(&b.lang) }
+    });
+}
+
+/// Returns the modification time of a file in milliseconds since the Unix epoch.
+pub fn file_mtime_ms(path: &Path) -> std::io::Result<u64> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    let since_epoch = modified.duration_since(UNIX_EPOCH).map_err(std::io::Error::other)?;
+    Ok(since_epoch.as_millis() as u64)
+}
+
+fn modified_ms(entry: &DirEntry) -> u64 {
+    file_mtime_ms(entry.path()).unwrap_or(0)
+}
+
+fn is_excluded(entry: &DirEntry, options: &ScanOptions) -> bool {
+    entry.depth() > 0
+        && entry.file_type().is_dir()
+        && options.exclude_dirs.iter().any(|d| entry.file_name() == d.as_str())
+}
+
+/// Applies the `.gitignore` files that affect a directory tree, each loaded on first use.
+struct GitignoreFilter {
+    /// The topmost directory whose `.gitignore` counts: the repository root, or the scanned
+    /// directory if it isn't inside a repository.
+    top: PathBuf,
+    matchers: HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl GitignoreFilter {
+    fn new(root: &Path) -> Self {
+        let top = root.ancestors().find(|dir| dir.join(".git").exists()).unwrap_or(root);
+        Self { top: top.to_path_buf(), matchers: HashMap::new() }
+    }
+
+    fn matcher(&mut self, dir: &Path) -> Option<&Gitignore> {
+        self.matchers.entry(dir.to_path_buf()).or_insert_with(|| {
+            let file = dir.join(".gitignore");
+            if !file.is_file() {
+                return None;
+            }
+            let (gitignore, error) = Gitignore::new(&file);
+            if let Some(e) = error {
+                tracing::warn!("Some patterns in {} were not understood: {}", file.display(), e);
+            }
+            Some(gitignore)
+        }).as_ref()
+    }
+
+    /// Like git, a deeper `.gitignore` takes precedence over those above it.
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        if is_dir && path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        for dir in path.ancestors().skip(1) {
+            let at_top = dir == self.top;
+            if let Some(matcher) = self.matcher(dir) {
+                match matcher.matched(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+            if at_top {
+                break;
+            }
+        }
+        false
+    }
+}
+
+pub struct ScanOutcome {
+    /// Sorted by `ScanOptions::sort_by`.
+    pub groups: Vec<ResxGroup>,
+    /// All files walked, not only .resx ones.
+    pub files_examined: usize,
+    /// Paths that could not be read (e.g. permission denied or a broken symlink), with the error.
+    pub errors: Vec<(String, String)>,
+}
+
+/// Walks `root` and groups every `.resx` file found. File paths are relative to their group's directory.
+/// Entries that can't be read are reported in `errors` and otherwise skipped.
+///
+/// Files are visited before subdirectories, so all files of a directory are seen together.
+/// `on_group` is called for each group as soon as its directory is done, with the number
+/// of files examined so far.
+pub fn scan_groups(root: &Path, options: &ScanOptions, on_group: impl FnMut(&ResxGroup, usize)) -> ScanOutcome {
+    scan_groups_cached(root, options, &HashMap::new(), on_group)
+}
+
+/// Like `scan_groups`, but reuses the details of files in `known` (keyed by absolute path)
+/// instead of reading them again if their modification time is unchanged.
+pub fn scan_groups_cached(root: &Path, options: &ScanOptions, known: &HashMap<PathBuf, ResxFile>, mut on_group: impl FnMut(&ResxGroup, usize)) -> ScanOutcome {
+    let mut walker = WalkDir::new(root).sort_by(|a, b| {
+        a.file_type().is_dir().cmp(&b.file_type().is_dir()).then_with(|| a.file_name().cmp(b.file_name()))
+    });
+    if let Some(depth) = options.max_depth {
+        walker = walker.max_depth(depth);
+    }
+
+    let mut result = Vec::new();
+    let mut current_dir: Option<PathBuf> = None;
+    let mut pending: HashMap<String, ResxGroup> = HashMap::new();
+    let mut files_scanned = 0;
+    let mut errors = Vec::new();
+
+    let mut flush = |pending: &mut HashMap<String, ResxGroup>, result: &mut Vec<ResxGroup>, files_scanned: usize| {
+        let mut groups: Vec<ResxGroup> = pending.drain().map(|(_, g)| g).collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        for mut group in groups {
+            sort_group_files(&mut group.files);
+            group.has_default_file = group.files.first().is_some_and(|f| f.lang == "default");
+            on_group(&group, files_scanned);
+            result.push(group);
+        }
+    };
+
+    let mut gitignore = options.respect_gitignore.then(|| GitignoreFilter::new(root));
+    let walk = walker.into_iter().filter_entry(move |e| {
+        let gitignored = e.depth() > 0 && gitignore.as_mut().is_some_and(|g| g.is_ignored(e.path(), e.file_type().is_dir()));
+        !is_excluded(e, options) && !gitignored
+    });
+    for entry in walk {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let path = e.path().map_or_else(|| root.to_string_lossy().to_string(), |p| p.to_string_lossy().to_string());
+                let message = match e.io_error() {
+                    Some(io) if io.kind() == std::io::ErrorKind::PermissionDenied => "Permission denied".to_string(),
+                    _ => e.to_string(),
+                };
+                errors.push((path, message));
+                continue;
+            }
+        };
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        let parent = path.parent().unwrap_or(Path::new(""));
+        if current_dir.as_deref() != Some(parent) {
+            flush(&mut pending, &mut result, files_scanned);
+            current_dir = Some(parent.to_path_buf());
+        }
+        files_scanned += 1;
+
+        if path.extension().and_then(|s| s.to_str()) == Some("resx") {
+            let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let directory = parent.to_string_lossy().to_string();
+            let (group_name, lang) = split_group_and_lang(file_stem);
+
+            pending.entry(group_name.clone()).or_insert(ResxGroup {
+                id: format!("{}::{}", directory, group_name),
+                name: group_name,
+                directory,
+                base_directory: root.to_string_lossy().to_string(),
+                files: Vec::new(),
+                has_default_file: false,
+            }).files.push({
+                let last_modified_ms = modified_ms(&entry);
+                let encoding = match known.get(path) {
+                    Some(file) if file.last_modified_ms == last_modified_ms && last_modified_ms != 0 => file.encoding,
+                    _ => crate::resx::detect_encoding(path).ok(),
+                };
+                ResxFile {
+                    path: entry.file_name().to_string_lossy().to_string(),
+                    lang,
+                    encoding,
+                    last_modified_ms,
+                }
+            });
+        }
+    }
+    flush(&mut pending, &mut result, files_scanned);
+
+    sort_groups(&mut result, options.sort_by);
+    ScanOutcome { groups: result, files_examined: files_scanned, errors }
+}
+
+/// Extensions of .NET project files.
+const PROJECT_EXTENSIONS: [&str; 3] = ["csproj", "vbproj", "fsproj"];
+
+/// Build output and tooling directories that never contain project sources.
+const NON_SOURCE_DIRS: [&str; 5] = ["bin", "obj", "node_modules", ".git", ".vs"];
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProjectSuggestion {
+    pub project_file: String,
+    /// The project's `Resources` subdirectory if it has one, otherwise the project directory.
+    pub resource_directory: String,
+    /// Number of .resx files below `resource_directory`, counting every language separately.
+    pub estimated_group_count: usize,
+}
+
+/// Finds the .NET projects below `root`, for when the user opened a solution directory
+/// instead of a project. Sorted by project file path.
+pub fn detect_projects(root: &Path) -> Vec<ProjectSuggestion> {
+    let is_source_dir = |e: &DirEntry| !(e.depth() > 0 && e.file_type().is_dir() && NON_SOURCE_DIRS.iter().any(|d| e.file_name() == *d));
+    let count_resx = |dir: &Path| WalkDir::new(dir).into_iter()
+        .filter_entry(is_source_dir)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().and_then(|s| s.to_str()) == Some("resx"))
+        .count();
+
+    let mut suggestions: Vec<ProjectSuggestion> = WalkDir::new(root).into_iter()
+        .filter_entry(is_source_dir)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().and_then(|s| s.to_str()).is_some_and(|ext| PROJECT_EXTENSIONS.contains(&ext)))
+        .map(|project| {
+            let project_dir = project.path().parent().unwrap_or(root);
+            let resources = project_dir.join("Resources");
+            let resource_directory = if resources.is_dir() { resources } else { project_dir.to_path_buf() };
+            ProjectSuggestion {
+                project_file: project.path().to_string_lossy().to_string(),
+                estimated_group_count: count_resx(&resource_directory),
+                resource_directory: resource_directory.to_string_lossy().to_string(),
+            }
+        })
+        .collect();
+    suggestions.sort_by(|a, b| a.project_file.cmp(&b.project_file));
+    suggestions
+}
+
+/// Sorts `groups` by `order`.
+pub fn sort_groups(groups: &mut [ResxGroup], order: GroupSortOrder) {
+    match order {
+        GroupSortOrder::Name => groups.sort_by(|a, b| a.name.cmp(&b.name)),
+        GroupSortOrder::Directory => groups.sort_by(|a, b| a.directory.cmp(&b.directory).then_with(|| a.name.cmp(&b.name))),
+        GroupSortOrder::KeyCount => {
+            let key_count = |group: &ResxGroup| group.files.first()
+                .map_or(0, |f| crate::resx::count_keys_streaming(&group.file_path(f), None).unwrap_or(0));
+            groups.sort_by_cached_key(|g| (std::cmp::Reverse(key_count(g)), g.name.clone()));
+        }
+        GroupSortOrder::FileCount => groups.sort_by(|a, b| b.files.len().cmp(&a.files.len()).then_with(|| a.name.cmp(&b.name))),
+        GroupSortOrder::LastModified => {
+            let last_modified = |group: &ResxGroup| group.files.iter().map(|f| f.last_modified_ms).max().unwrap_or(0);
+            groups.sort_by(|a, b| last_modified(b).cmp(&last_modified(a)).then_with(|| a.name.cmp(&b.name)));
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct ScanDiff {
+    pub added_groups: Vec<ResxGroup>,
+    /// Ids of groups that no longer exist.
+    pub removed_groups: Vec<String>,
+    pub changed_groups: Vec<ResxGroup>,
+}
+
+/// Absolute paths and modification times of a group's files, in a comparable order.
+fn file_signature(group: &ResxGroup) -> Vec<(PathBuf, u64)> {
+    let mut signature: Vec<(PathBuf, u64)> = group.files.iter().map(|f| (group.file_path(f), f.last_modified_ms)).collect();
+    signature.sort();
+    signature
+}
+
+/// Compares a new scan with a previous one. A group has changed if a file was added,
+/// removed or modified; unchanged groups are left out.
+pub fn diff_scans(previous: &[ResxGroup], current: Vec<ResxGroup>) -> ScanDiff {
+    let previous_by_id: HashMap<&str, &ResxGroup> = previous.iter().map(|g| (g.id.as_str(), g)).collect();
+    let mut diff = ScanDiff {
+        removed_groups: previous.iter()
+            .filter(|p| !current.iter().any(|c| c.id == p.id))
+            .map(|p| p.id.clone())
+            .collect(),
+        ..Default::default()
+    };
+    for group in current {
+        match previous_by_id.get(group.id.as_str()) {
+            None => diff.added_groups.push(group),
+            Some(old) if file_signature(old) != file_signature(&group) => diff.changed_groups.push(group),
+            Some(_) => {}
+        }
+    }
+    diff
+}
+
+/// Scans `root` again and reports how it differs from `previous`. The tree is still walked to
+/// find added and removed files, but files whose modification time matches the previous scan
+/// keep their detected encoding instead of being read again. `options` should be the ones
+/// `previous` was scanned with.
+pub fn rescan(root: &Path, options: &ScanOptions, previous: &[ResxGroup]) -> ScanDiff {
+    let known: HashMap<PathBuf, ResxFile> = previous.iter()
+        .flat_map(|group| group.files.iter().map(move |file| (group.file_path(file), file.clone())))
+        .collect();
+    let groups = scan_groups_cached(root, options, &known, |_, _| {}).groups;
+    diff_scans(previous, groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_files(root: &Path, files: &[(&str, &str)]) -> std::io::Result<()> {
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+        Ok(())
+    }
+
+    /// Names of the groups found under `root`, with their directory relative to it.
+    fn scanned_groups(root: &Path, respect_gitignore: bool) -> Vec<String> {
+        let options = ScanOptions { respect_gitignore, ..Default::default() };
+        let mut groups: Vec<String> = scan_groups(root, &options, |_, _| {}).groups.iter()
+            .map(|g| {
+                let dir = Path::new(&g.directory).strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+                if dir.is_empty() { g.name.clone() } else { format!("{}/{}", dir, g.name) }
+            })
+            .collect();
+        groups.sort();
+        groups
+    }
+
+    fn file_names(paths: &[String]) -> Vec<String> {
+        paths.iter()
+            .map(|p| Path::new(p).file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_canonical_lang_code() {
+        assert_eq!(canonical_lang_code("en"), "en");
+        assert_eq!(canonical_lang_code("EN-us"), "en-US");
+        assert_eq!(canonical_lang_code("ZH-HANS"), "zh-Hans");
+        assert_eq!(canonical_lang_code("az-latn-az"), "az-Latn-AZ");
+        assert_eq!(canonical_lang_code("es-419"), "es-419");
+    }
+
+    #[test]
+    fn test_plan_language_renames() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        for name in ["Strings.resx", "Strings.en-us.resx", "Strings.zh-Hans.resx", "Other.fr-fr.resx"] {
+            fs::write(dir.path().join(name), "")?;
+        }
+
+        let plan = plan_language_renames(dir.path(), "Strings")?;
+        let old: Vec<String> = plan.renames.iter().map(|op| op.old_path.clone()).collect();
+        let new: Vec<String> = plan.renames.iter().map(|op| op.new_path.clone()).collect();
+        assert_eq!(file_names(&old), ["Strings.en-us.resx"]);
+        assert_eq!(file_names(&new), ["Strings.en-US.resx"]);
+        assert!(plan.skipped.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_language_renames_skips_collisions() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        // Only a case-sensitive file system can hold both names
+        fs::write(dir.path().join("Strings.en-US.resx"), "")?;
+        if fs::write(dir.path().join("Strings.en-us.resx"), "").is_err() || fs::read_dir(dir.path())?.count() < 2 {
+            return Ok(());
+        }
+        fs::write(dir.path().join("Strings.EN-us.resx"), "")?;
+
+        let plan = plan_language_renames(dir.path(), "Strings")?;
+        assert!(plan.renames.is_empty());
+        let skipped: Vec<String> = plan.skipped.iter().map(|op| op.old_path.clone()).collect();
+        assert_eq!(file_names(&skipped), ["Strings.EN-us.resx", "Strings.en-us.resx"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_language_renames_skips_shared_target() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("Strings.en-us.resx"), "")?;
+        if fs::write(dir.path().join("Strings.EN-us.resx"), "").is_err() || fs::read_dir(dir.path())?.count() < 2 {
+            return Ok(());
+        }
+
+        // Both would become Strings.en-US.resx; only the first may take the name
+        let plan = plan_language_renames(dir.path(), "Strings")?;
+        assert_eq!(plan.renames.len(), 1);
+        assert_eq!(plan.skipped.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_skips_gitignored_directory() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join(".git"))?;
+        write_files(dir.path(), &[
+            (".gitignore", "bin/\n"),
+            ("Strings.resx", ""),
+            ("bin/Debug/Strings.resx", ""),
+        ])?;
+
+        assert_eq!(scanned_groups(dir.path(), true), ["Strings"]);
+        assert_eq!(scanned_groups(dir.path(), false), ["Strings", "bin/Debug/Strings"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_nested_gitignore_negation() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join(".git"))?;
+        write_files(dir.path(), &[
+            (".gitignore", "Generated*.resx\n"),
+            ("Generated.resx", ""),
+            ("Strings.resx", ""),
+            // A deeper .gitignore takes precedence
+            ("Keep/.gitignore", "!Generated.resx\n"),
+            ("Keep/Generated.resx", ""),
+            ("Keep/GeneratedOther.resx", ""),
+        ])?;
+
+        assert_eq!(scanned_groups(dir.path(), true), ["Keep/Generated", "Strings"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_below_repository_root() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join(".git"))?;
+        write_files(dir.path(), &[
+            (".gitignore", "obj/\n"),
+            ("src/Strings.resx", ""),
+            ("src/obj/Strings.resx", ""),
+        ])?;
+
+        // The repository root's .gitignore applies when scanning one of its subdirectories
+        let src = dir.path().join("src");
+        assert_eq!(scanned_groups(&src, true), ["Strings"]);
+
+        // Outside a repository, a parent directory's .gitignore doesn't count
+        fs::remove_dir(dir.path().join(".git"))?;
+        assert_eq!(scanned_groups(&src, true), ["Strings", "obj/Strings"]);
+        Ok(())
+    }
+
+    fn group_names(groups: &[ResxGroup]) -> Vec<String> {
+        groups.iter().map(|g| g.name.clone()).collect()
+    }
+
+    /// Moves a file's modification time forward so a rescan sees it as modified.
+    fn touch(path: &Path) -> std::io::Result<()> {
+        let modified = fs::metadata(path)?.modified()? + std::time::Duration::from_secs(10);
+        fs::File::options().write(true).open(path)?.set_modified(modified)
+    }
+
+    #[test]
+    fn test_diff_scans() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        write_files(dir.path(), &[
+            ("Changed.resx", ""),
+            ("Changed.fr.resx", ""),
+            ("Extended.resx", ""),
+            ("Removed.resx", ""),
+            ("Same.resx", ""),
+        ])?;
+        let previous = scan_groups(dir.path(), &ScanOptions::default(), |_, _| {}).groups;
+
+        touch(&dir.path().join("Changed.fr.resx"))?;
+        write_files(dir.path(), &[("Added.resx", ""), ("Extended.de.resx", "")])?;
+        fs::remove_file(dir.path().join("Removed.resx"))?;
+
+        let diff = rescan(dir.path(), &ScanOptions::default(), &previous);
+        assert_eq!(group_names(&diff.added_groups), ["Added"]);
+        assert_eq!(diff.removed_groups, [format!("{}::Removed", dir.path().to_string_lossy())]);
+        assert_eq!(group_names(&diff.changed_groups), ["Changed", "Extended"]);
+
+        // Nothing is reported when the tree hasn't changed
+        let current = scan_groups(dir.path(), &ScanOptions::default(), |_, _| {}).groups;
+        let diff = rescan(dir.path(), &ScanOptions::default(), &current);
+        assert!(diff.added_groups.is_empty() && diff.removed_groups.is_empty() && diff.changed_groups.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescan_with_scan_options() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        write_files(dir.path(), &[("Strings.resx", ""), ("bin/Strings.resx", "")])?;
+        let options = ScanOptions { exclude_dirs: vec!["bin".to_string()], ..Default::default() };
+        let previous = scan_groups(dir.path(), &options, |_, _| {}).groups;
+
+        // What the options left out of the previous scan is not reported as added
+        let diff = rescan(dir.path(), &options, &previous);
+        assert!(diff.added_groups.is_empty() && diff.removed_groups.is_empty() && diff.changed_groups.is_empty());
+        assert_eq!(group_names(&rescan(dir.path(), &ScanOptions::default(), &previous).added_groups), ["Strings"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescan_reuses_unchanged_files() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        write_files(dir.path(), &[("Strings.resx", ""), ("Strings.fr.resx", "")])?;
+        let mut previous = scan_groups(dir.path(), &ScanOptions::default(), |_, _| {}).groups;
+        for file in &mut previous[0].files {
+            file.encoding = Some(crate::resx::FileEncoding::Utf16Le);
+        }
+
+        touch(&dir.path().join("Strings.fr.resx"))?;
+        let known: HashMap<PathBuf, ResxFile> = previous[0].files.iter()
+            .map(|f| (previous[0].file_path(f), f.clone()))
+            .collect();
+        let groups = scan_groups_cached(dir.path(), &ScanOptions::default(), &known, |_, _| {}).groups;
+
+        // The unmodified file keeps what the previous scan recorded; the modified one is read again
+        let encodings: Vec<_> = groups[0].files.iter().map(|f| (f.path.as_str(), f.encoding)).collect();
+        assert_eq!(encodings, [
+            ("Strings.resx", Some(crate::resx::FileEncoding::Utf16Le)),
+            ("Strings.fr.resx", crate::resx::detect_encoding(&dir.path().join("Strings.fr.resx")).ok()),
+        ]);
+        Ok(())
+    }
+}
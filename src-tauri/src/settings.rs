@@ -1,47 +1,211 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use tauri::AppHandle;
 use tauri::Manager;
+use crate::resx::ResxWriteOptions;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
 pub struct SavedGroup {
     pub name: String,
     pub directory: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct AppSettings {
     pub saved_groups: Vec<SavedGroup>,
     pub theme: String, // "light" or "dark"
+    pub watch_debounce_ms: u64,
+    pub editor_font_size: u32,
+    /// When there are more saved groups than this, the oldest ones (at the start of the list)
+    /// are dropped when saving.
+    pub max_saved_groups: usize,
+    /// Show non-string resources (images, binary data) in the grid, with their content truncated.
+    pub show_binary_entries: bool,
+    /// Formatting of keys added to files and of newly created files.
+    pub write_options: ResxWriteOptions,
+    /// How many operations the in-memory operation log keeps. Takes effect on restart.
+    pub operation_log_size: usize,
+    /// Readable names for language codes, shown as column headers ("zh-Hans-CN" -> "Simplified Chinese (China)").
+    /// Only used by the frontend.
+    pub language_display_names: HashMap<String, String>,
+    /// Regular expressions for keys that are hidden from the grid, such as designer metadata.
+    /// A key is hidden if a pattern matches all of it.
+    pub hidden_key_patterns: Vec<String>,
 }
 
-fn get_settings_path(app: &AppHandle) -> Option<PathBuf> {
-    app.path().app_config_dir().ok().map(|p| p.join("settings.json"))
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            saved_groups: Vec::new(),
+            theme: String::new(),
+            watch_debounce_ms: 300,
+            editor_font_size: 14,
+            max_saved_groups: 50,
+            show_binary_entries: false,
+            write_options: ResxWriteOptions::default(),
+            operation_log_size: 100,
+            language_display_names: default_language_display_names(),
+            hidden_key_patterns: vec![">>.*".to_string(), r"\$this\..*".to_string(), r"\$[A-Z].*".to_string()],
+        }
+    }
 }
 
-pub fn load_settings(app: &AppHandle) -> AppSettings {
-    if let Some(path) = get_settings_path(app) {
-        if path.exists() {
-            if let Ok(content) = fs::read_to_string(path) {
-                if let Ok(settings) = serde_json::from_str(&content) {
-                    return settings;
-                }
-            }
+/// Compiles `hidden_key_patterns` so that each one has to match a whole key.
+pub fn hidden_key_regexes(patterns: &[String]) -> Result<Vec<Regex>, String> {
+    patterns.iter()
+        .map(|p| Regex::new(&format!("^(?:{})$", p)).map_err(|e| format!("Invalid hidden key pattern '{}': {}", p, e)))
+        .collect()
+}
+
+fn default_language_display_names() -> HashMap<String, String> {
+    [
+        ("de", "German"),
+        ("en", "English"),
+        ("en-US", "English (United States)"),
+        ("en-GB", "English (United Kingdom)"),
+        ("es", "Spanish"),
+        ("fr", "French"),
+        ("it", "Italian"),
+        ("ja", "Japanese"),
+        ("ko", "Korean"),
+        ("nl", "Dutch"),
+        ("pl", "Polish"),
+        ("pt-BR", "Portuguese (Brazil)"),
+        ("ru", "Russian"),
+        ("tr", "Turkish"),
+        ("zh-Hans", "Simplified Chinese"),
+        ("zh-Hans-CN", "Simplified Chinese (China)"),
+        ("zh-Hant", "Traditional Chinese"),
+        ("zh-Hant-TW", "Traditional Chinese (Taiwan)"),
+    ]
+    .into_iter()
+    .map(|(code, name)| (code.to_string(), name.to_string()))
+    .collect()
+}
+
+fn get_settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|p| p.join("settings.json"))
+        .map_err(|e| format!("Could not determine settings path: {}", e))
+}
+
+/// Why the default settings are used instead of the saved ones.
+#[derive(Serialize, Debug, Clone)]
+pub enum DefaultReason {
+    /// There is no settings file yet.
+    FirstLaunch,
+    /// The settings file exists but couldn't be read or isn't valid settings JSON.
+    ParseError(String),
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub enum LoadSettingsResult {
+    Ok(AppSettings),
+    Default(DefaultReason),
+}
+
+impl LoadSettingsResult {
+    pub fn into_settings(self) -> AppSettings {
+        match self {
+            LoadSettingsResult::Ok(settings) => settings,
+            LoadSettingsResult::Default(_) => AppSettings::default(),
         }
     }
-    AppSettings::default()
 }
 
+/// Loads the saved settings, telling a first launch apart from a settings file that is broken.
+pub fn try_load_settings(app: &AppHandle) -> LoadSettingsResult {
+    let path = match get_settings_path(app) {
+        Ok(path) => path,
+        Err(e) => return LoadSettingsResult::Default(DefaultReason::ParseError(e)),
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return LoadSettingsResult::Default(DefaultReason::FirstLaunch),
+        Err(e) => return LoadSettingsResult::Default(DefaultReason::ParseError(format!("Cannot read {}: {}", path.display(), e))),
+    };
+    match serde_json::from_str(&content) {
+        Ok(settings) => LoadSettingsResult::Ok(settings),
+        Err(e) => LoadSettingsResult::Default(DefaultReason::ParseError(format!("{}: {}", path.display(), e))),
+    }
+}
+
+/// The saved settings, or the defaults if there are none or they can't be loaded.
+pub fn load_settings(app: &AppHandle) -> AppSettings {
+    let result = try_load_settings(app);
+    if let LoadSettingsResult::Default(DefaultReason::ParseError(e)) = &result {
+        tracing::error!("Using default settings: {}", e);
+    }
+    result.into_settings()
+}
+
+fn validate_settings(settings: &AppSettings) -> Result<(), String> {
+    if !(8..=72).contains(&settings.editor_font_size) {
+        return Err("Font size must be between 8 and 72".to_string());
+    }
+    if settings.max_saved_groups == 0 {
+        return Err("At least one saved group must be allowed".to_string());
+    }
+    hidden_key_regexes(&settings.hidden_key_patterns)?;
+    Ok(())
+}
+
+/// Writes `settings.json.tmp` and renames it over `settings.json`, so a crash mid-write
+/// leaves the previous settings intact instead of a truncated file.
 pub fn save_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
-    if let Some(path) = get_settings_path(app) {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    validate_settings(settings)?;
+    let path = get_settings_path(app)?;
+    let parent = path.parent().ok_or_else(|| format!("{} has no parent directory", path.display()))?;
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    let tmp_path = parent.join("settings.json.tmp");
+
+    let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    if let Err(e) = fs::write(&tmp_path, content).and_then(|_| fs::rename(&tmp_path, &path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Could not save settings: {}", e));
+    }
+    Ok(())
+}
+
+/// Drops the oldest of `groups`, those at the start of the list, until at most `max` remain.
+/// Returns how many were dropped.
+pub fn drop_oldest_groups(groups: &mut Vec<SavedGroup>, max: usize) -> usize {
+    let dropped = groups.len().saturating_sub(max);
+    groups.drain(..dropped);
+    dropped
+}
+
+/// Drops the oldest saved groups until at most `max` remain, returning how many were dropped.
+pub fn trim_saved_groups(app: &AppHandle, max: usize) -> Result<usize, String> {
+    let mut settings = load_settings(app);
+    let dropped = drop_oldest_groups(&mut settings.saved_groups, max);
+    save_settings(app, &settings)?;
+    Ok(dropped)
+}
+
+/// Replaces the saved group list with `new_order`, which must contain exactly the
+/// currently saved groups (same names and directories), just in a different order.
+pub fn reorder_saved_groups(app: &AppHandle, new_order: Vec<SavedGroup>) -> Result<(), String> {
+    let mut settings = load_settings(app);
+
+    let mut remaining = settings.saved_groups.clone();
+    for group in &new_order {
+        match remaining.iter().position(|g| g == group) {
+            Some(idx) => {
+                remaining.swap_remove(idx);
+            }
+            None => return Err(format!("Group '{}' in '{}' is not a saved group", group.name, group.directory)),
         }
-        let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
-        fs::write(path, content).map_err(|e| e.to_string())?;
-        Ok(())
-    } else {
-        Err("Could not determine settings path".to_string())
     }
+    if !remaining.is_empty() {
+        return Err(format!("New order is missing {} saved group(s)", remaining.len()));
+    }
+
+    settings.saved_groups = new_order;
+    save_settings(app, &settings)
 }
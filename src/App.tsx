@@ -1,11 +1,18 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Toaster } from 'sonner';
+import { Toaster, toast } from 'sonner';
 import { Sidebar } from "./components/Sidebar";
 import { ResourceGrid } from "./components/ResourceGrid";
-import { ResxGroup } from "./types";
+import { ResxFileEvent, ResxGroup, ScanResult } from "./types";
 import "./App.css";
 
+interface ResxWriteOptions {
+    indent: { Spaces: number } | "Tabs";
+    preserve_original_indent: boolean;
+    newline: "Lf" | "Crlf" | "Preserve";
+    xml_declaration: boolean;
+}
+
 interface SavedGroup {
     name: string;
     directory: string;
@@ -14,20 +21,48 @@ interface SavedGroup {
 interface AppSettings {
     saved_groups: SavedGroup[];
     theme: string;
+    watch_debounce_ms: number;
+    editor_font_size: number;
+    max_saved_groups: number;
+    show_binary_entries: boolean;
+    write_options: ResxWriteOptions;
+    operation_log_size: number;
+    language_display_names: Record<string, string>;
+    hidden_key_patterns: string[];
 }
 
+// Serialized settings::LoadSettingsResult
+type LoadSettingsResult =
+    | { Ok: AppSettings }
+    | { Default: "FirstLaunch" | { ParseError: string } };
+
 function App() {
   const [groups, setGroups] = useState<ResxGroup[]>([]);
   const [selectedGroup, setSelectedGroup] = useState<ResxGroup | null>(null);
   const [isDark, setIsDark] = useState(false);
+  const [languageNames, setLanguageNames] = useState<Record<string, string>>({});
   const [settingsLoaded, setSettingsLoaded] = useState(false);
+  // Keeps settings this component doesn't edit so saving doesn't reset them
+  const loadedSettings = useRef<Partial<AppSettings>>({});
+  // How many groups the last save left out, so the warning is only shown when that grows
+  const droppedGroups = useRef(0);
 
   // Load settings on startup
   useEffect(() => {
       async function loadSettings() {
           try {
-              const settings = await invoke<AppSettings>('get_app_settings');
+              const result = await invoke<LoadSettingsResult>('get_app_settings');
+              if (!('Ok' in result)) {
+                  if (result.Default !== 'FirstLaunch') {
+                      console.error("Settings file is corrupt:", result.Default.ParseError);
+                      toast.warning('Your settings file was corrupt and has been reset');
+                  }
+                  return;
+              }
+              const settings = result.Ok;
+              loadedSettings.current = settings;
               setIsDark(settings.theme === 'dark');
+              setLanguageNames(settings.language_display_names ?? {});
               
               if (settings.saved_groups && settings.saved_groups.length > 0) {
                   const uniqueDirs = Array.from(new Set(settings.saved_groups.map(g => g.directory)));
@@ -35,7 +70,7 @@ function App() {
                   
                   for (const path of uniqueDirs) {
                       try {
-                          const result = await invoke<ResxGroup[]>('scan_directory', { path });
+                          const result = (await invoke<ScanResult>('scan_directory', { path })).groups;
                           // Filter to only keep saved groups
                           const savedInThisDir = settings.saved_groups.filter(g => g.directory === path);
                           const keptGroups = result.filter(r => 
@@ -61,12 +96,20 @@ function App() {
   useEffect(() => {
       if (!settingsLoaded) return;
 
-      const settings: AppSettings = {
+      const settings = {
+          ...loadedSettings.current,
           saved_groups: groups.map(g => ({ name: g.name, directory: g.directory })),
           theme: isDark ? 'dark' : 'light'
       };
 
-      invoke('save_app_settings', { settings }).catch(console.error);
+      invoke<number>('save_app_settings', { settings })
+          .then(dropped => {
+              if (dropped > droppedGroups.current) {
+                  toast.warning(`The saved group limit was reached; the ${dropped} oldest group(s) won't be restored on restart`);
+              }
+              droppedGroups.current = dropped;
+          })
+          .catch(console.error);
   }, [groups, isDark, settingsLoaded]);
 
   useEffect(() => {
@@ -77,6 +120,30 @@ function App() {
       }
   }, [isDark]);
 
+  const replaceGroup = (oldGroup: ResxGroup, newGroup: ResxGroup) => {
+      setGroups(prev => prev.map(g => g === oldGroup ? newGroup : g));
+      setSelectedGroup(prev => prev === oldGroup ? newGroup : prev);
+  };
+
+  const handleFileAddedOrRemoved = async (group: ResxGroup, event: ResxFileEvent) => {
+      if (event.kind === 'Deleted') {
+          const files = group.files.filter(f => f.path !== event.path);
+          if (files.length !== group.files.length) {
+              replaceGroup(group, { ...group, files, has_default_file: files.some(f => f.lang === 'default') });
+          }
+          return;
+      }
+      try {
+          const result = await invoke<ScanResult>('scan_directory', { path: group.directory });
+          const rescanned = result.groups.find(g => g.id === group.id);
+          if (rescanned) {
+              replaceGroup(group, rescanned);
+          }
+      } catch (e) {
+          console.error(`Failed to rescan ${group.directory}:`, e);
+      }
+  };
+
   const handleRemoveGroup = (groupToRemove: ResxGroup) => {
       setGroups(prev => prev.filter(g => g !== groupToRemove));
       if (selectedGroup === groupToRemove) {
@@ -98,7 +165,7 @@ function App() {
       />
       
       {selectedGroup ? (
-        <ResourceGrid group={selectedGroup} isDark={isDark} />
+        <ResourceGrid group={selectedGroup} isDark={isDark} languageNames={languageNames} onFileAddedOrRemoved={handleFileAddedOrRemoved} />
       ) : (
         <div className="flex-1 flex items-center justify-center text-muted-foreground">
           <div className="text-center">
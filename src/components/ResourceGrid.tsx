@@ -4,7 +4,7 @@ import type { Column, RenderEditCellProps, DataGridHandle } from 'react-data-gri
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { ask } from '@tauri-apps/plugin-dialog';
-import { ResxGroup, RowData } from '../types';
+import { LoadedGroup, ResxFileEvent, ResxGroup, RowData } from '../types';
 import { Plus, Search, Filter } from 'lucide-react';
 import 'react-data-grid/lib/styles.css';
 import { toast } from 'sonner';
@@ -17,11 +17,13 @@ import { cn } from '../lib/utils';
 interface ResourceGridProps {
     group: ResxGroup;
     isDark: boolean;
+    languageNames: Record<string, string>; // lang code -> display name for column headers
+    onFileAddedOrRemoved: (group: ResxGroup, event: ResxFileEvent) => void;
 }
 
 type HistoryAction = 
     | { type: 'update', key: string, lang: string, oldValue: string, newValue: string }
-    | { type: 'rename', oldKey: string, newKey: string }
+    | { type: 'rename', oldKey: string, newKey: string, paths: string[] }
     | { type: 'add', key: string }
     | { type: 'delete', key: string, row: RowData, indices?: Record<string, number> }
     | { type: 'batch', actions: HistoryAction[] };
@@ -61,7 +63,7 @@ function TextEditor({ row, column, onRowChange, onClose }: RenderEditCellProps<R
     );
 }
 
-export function ResourceGrid({ group, isDark }: ResourceGridProps) {
+export function ResourceGrid({ group, isDark, languageNames, onFileAddedOrRemoved }: ResourceGridProps) {
     const [rows, setRows] = useState<RowData[]>([]);
     const [filterText, setFilterText] = useState('');
     const [showEmptyOnly, setShowEmptyOnly] = useState(false);
@@ -93,6 +95,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
                 const file = group.files.find(f => f.lang === action.lang);
                 if (file) {
                     await invoke('update_resource', {
+                        directory: group.directory,
                         path: file.path,
                         key: action.key,
                         value: action.oldValue
@@ -101,14 +104,14 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
                 break;
             }
             case 'rename': {
-                await Promise.all(group.files.map(f => 
-                    invoke('rename_key', { path: f.path, old_key: action.newKey, new_key: action.oldKey })
+                await Promise.all(action.paths.map(path => 
+                    invoke('rename_key', { directory: group.directory, path, old_key: action.newKey, new_key: action.oldKey })
                 ));
                 break;
             }
             case 'add': {
                 await Promise.all(group.files.map(f => 
-                    invoke('remove_key', { path: f.path, key: action.key })
+                    invoke('remove_key', { directory: group.directory, path: f.path, key: action.key })
                 ));
                 break;
             }
@@ -117,7 +120,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
                 await Promise.all(group.files.map(f => {
                     const index = action.indices && action.indices[f.path] !== undefined ? action.indices[f.path] : 0;
                     const value = action.row.values[f.lang] || "";
-                    return invoke('insert_key', { path: f.path, key: action.key, value, index });
+                    return invoke('insert_key', { directory: group.directory, path: f.path, key: action.key, value, index });
                 }));
                 break;
             }
@@ -163,7 +166,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
                         }
                         
                         await Promise.all(Array.from(insertsByFile.entries()).map(([path, items]) => 
-                            invoke('batch_insert_keys', { path, items })
+                            invoke('batch_insert_keys', { directory: group.directory, path, items })
                         ));
                         
                         toast.success(`Restored ${deleteActions.length} keys`, { id: toastId });
@@ -188,7 +191,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
                         }
 
                         await Promise.all(Array.from(updatesByPath.entries()).map(([path, updates]) => 
-                            invoke('batch_update_resources', { path, updates })
+                            invoke('batch_update_resources', { directory: group.directory, path, updates })
                         ));
                          
                         toast.success(`Restored ${updateActions.length} values`, { id: toastId });
@@ -294,7 +297,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
             const colIdx = i + 1; // 0 is key
             return {
                 key: `values.${file.lang}`,
-                name: file.lang === 'default' ? 'Default' : file.lang,
+                name: file.lang === 'default' ? 'Default' : (languageNames[file.lang] ?? file.lang),
                 editable: true,
                 width: 300,
                 resizable: true,
@@ -388,7 +391,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
         };
 
         return [keyCol, ...langCols];
-    }, [group, filterText, selectionRange, filteredRows]); // Re-render when selection changes
+    }, [group, filterText, selectionRange, filteredRows, languageNames]); // Re-render when selection changes
 
     useEffect(() => {
         const handleClick = () => setContextMenu(null);
@@ -397,12 +400,18 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
     }, []);
 
     useEffect(() => {
-        loadData();
+        // Opening a group starts a new change summary
+        loadData(true);
         invoke('watch_group', { directory: group.directory }).catch(console.error);
 
         let debounceTimer: number | undefined;
 
-        const unlistenPromise = listen('resx-changed', () => {
+        const unlistenPromise = listen<ResxFileEvent>('resx-file-event', ({ payload }) => {
+            // New or removed files change the group itself; the parent updates it, which reloads the grid
+            if (payload.kind === 'Created' || payload.kind === 'Deleted') {
+                onFileAddedOrRemoved(group, payload);
+                return;
+            }
             if (debounceTimer) {
                 clearTimeout(debounceTimer);
             }
@@ -419,9 +428,9 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
         };
     }, [group]);
 
-    async function loadData() {
+    async function loadData(resetSnapshots = false) {
         try {
-            const data = await invoke<RowData[]>('load_group', { files: group.files });
+            const { rows: data } = await invoke<LoadedGroup>('load_group', { group, options: { reset_snapshots: resetSnapshots } });
             data.sort((a, b) => a.key.localeCompare(b.key));
             setRows(data);
         } catch (e) {
@@ -434,7 +443,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
         
         try {
             await Promise.all(group.files.map(f => 
-                 invoke('add_key', { path: f.path, key: newKeyName })
+                 invoke('add_key', { directory: group.directory, path: f.path, key: newKeyName })
             ));
             pushHistory({ type: 'add', key: newKeyName });
             setNewKeyName('');
@@ -458,6 +467,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
 
         try {
             await invoke('update_resource', {
+                directory: group.directory,
                 path: file.path,
                 key: row.key,
                 value: ""
@@ -478,8 +488,9 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
          try {
             const indices: Record<string, number> = {};
             await Promise.all(group.files.map(async f => {
-                 const index = await invoke<number>('remove_key', { path: f.path, key: keyToDelete });
-                 indices[f.path] = index;
+                 // remove_key only returns how many entries were removed; the index is needed for undo
+                 const result = await invoke<Record<string, number>>('batch_remove_keys', { directory: group.directory, path: f.path, keys: [keyToDelete] });
+                 if (result[keyToDelete] !== undefined) indices[f.path] = result[keyToDelete];
             }));
             pushHistory({ type: 'delete', key: keyToDelete, row: rowToDelete, indices });
             setDeleteKeyDialogOpen(false);
@@ -554,6 +565,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
 
                         await Promise.all(group.files.map(async f => {
                              const result = await invoke<Record<string, number>>('batch_remove_keys', { 
+                                 directory: group.directory,
                                  path: f.path, 
                                  keys: keys 
                              });
@@ -616,7 +628,7 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
                          }
 
                          await Promise.all(Array.from(updatesByFile.entries()).map(([path, updates]) => 
-                             invoke('batch_update_resources', { path, updates })
+                             invoke('batch_update_resources', { directory: group.directory, path, updates })
                          ));
                          
                          pushHistory({ type: 'batch', actions: batchActions });
@@ -641,10 +653,12 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
         try {
             if (column.key === 'key') {
                 if (updatedRow.key !== oldRow.key) {
-                     await Promise.all(group.files.map(f => 
-                        invoke('rename_key', { path: f.path, old_key: oldRow.key, new_key: updatedRow.key })
+                     // Renaming fails in files that don't have the key, so only touch those that do
+                     const paths = oldRow.source_files;
+                     await Promise.all(paths.map(path => 
+                        invoke('rename_key', { directory: group.directory, path, old_key: oldRow.key, new_key: updatedRow.key })
                     ));
-                    pushHistory({ type: 'rename', oldKey: oldRow.key, newKey: updatedRow.key });
+                    pushHistory({ type: 'rename', oldKey: oldRow.key, newKey: updatedRow.key, paths });
                 }
             } else {
                 const lang = column.key.replace('values.', '');
@@ -653,12 +667,25 @@ export function ResourceGrid({ group, isDark }: ResourceGridProps) {
                 
                 if (newValue !== oldValue) {
                     const file = group.files.find(f => f.lang === lang);
-                    if (file) {
+                    if (file && oldRow.source_files.includes(file.path)) {
                         await invoke('update_resource', {
+                            directory: group.directory,
+                            path: file.path,
+                            key: updatedRow.key,
+                            value: newValue
+                        });
+                        pushHistory({ type: 'update', key: updatedRow.key, lang, oldValue, newValue });
+                    } else if (file) {
+                        // This language's file doesn't have the key yet, so updating it would fail
+                        await invoke('add_key_with_comment', {
+                            directory: group.directory,
                             path: file.path,
                             key: updatedRow.key,
                             value: newValue
                         });
+                        setRows(prev => prev.map(r => r.key === updatedRow.key
+                            ? { ...r, source_files: [...r.source_files, file.path] }
+                            : r));
                         pushHistory({ type: 'update', key: updatedRow.key, lang, oldValue, newValue });
                     }
                 }
@@ -2,7 +2,7 @@ import React from 'react';
 import { open } from '@tauri-apps/plugin-dialog';
 import { invoke } from '@tauri-apps/api/core';
 import { toast } from 'sonner';
-import { ResxGroup } from '../types';
+import { ResxGroup, ScanResult } from '../types';
 import { FolderOpen, Moon, Sun, X } from 'lucide-react';
 import { Button } from './ui/button';
 
@@ -24,8 +24,14 @@ export function Sidebar({ groups, selectedGroup, onSelectGroup, setGroups, isDar
                 multiple: false,
             });
             if (selected && typeof selected === 'string') {
-                const result = await invoke<ResxGroup[]>('scan_directory', { path: selected });
-                
+                const scan = await invoke<ScanResult>('scan_directory', { path: selected });
+                const result = scan.groups;
+
+                if (scan.scan_errors.length > 0) {
+                    console.warn('Scan errors:', scan.scan_errors);
+                    toast.warning(`Could not access ${scan.scan_errors.length} ${scan.scan_errors.length === 1 ? 'directory' : 'directories'}`);
+                }
+
                 if (result.length === 0) {
                     toast.error('No .resx files found in the selected folder');
                     return;
@@ -74,10 +80,10 @@ export function Sidebar({ groups, selectedGroup, onSelectGroup, setGroups, isDar
             <div className="flex-1 overflow-y-auto">
                 {groups.map((group) => (
                     <div
-                        key={group.name + group.directory}
+                        key={group.id}
                         onClick={() => onSelectGroup(group)}
                         className={`p-3 cursor-pointer hover:bg-accent hover:text-accent-foreground transition-colors group relative ${
-                            selectedGroup?.name === group.name && selectedGroup?.directory === group.directory ? 'bg-accent text-accent-foreground border-l-4 border-primary' : 'text-muted-foreground'
+                            selectedGroup?.id === group.id ? 'bg-accent text-accent-foreground border-l-4 border-primary' : 'text-muted-foreground'
                         }`}
                     >
                         <div className="font-medium text-foreground pr-6">{group.name}</div>
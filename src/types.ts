@@ -1,15 +1,45 @@
+export type FileEncoding = 'Utf8' | 'Utf8Bom' | 'Utf16Le' | 'Utf16Be' | 'Unknown';
+
 export interface ResxFile {
-    path: string;
+    path: string; // relative to the group's directory as returned by a scan
     lang: string;
+    encoding?: FileEncoding | null;
+    last_modified_ms?: number;
 }
 
 export interface ResxGroup {
+    id: string;
     name: string;
     directory: string;
+    base_directory: string;
     files: ResxFile[];
+    has_default_file: boolean;
+}
+
+export interface ScanResult {
+    groups: ResxGroup[];
+    scan_duration_ms: number;
+    files_examined: number;
+    scan_errors: [string, string][]; // [path, error message]
 }
 
 export interface RowData {
     key: string;
     values: Record<string, string>; // lang -> value
+    is_consistent: boolean; // all non-empty values are the same, likely untranslated
+    type_attr?: string | null; // resource type of binary entries, whose values are truncated
+    source_files: string[]; // paths of the files containing this key
+    comments: Record<string, string | null>; // lang -> <comment>, for languages whose file has the key
+}
+
+export interface LoadedGroup {
+    rows: RowData[];
+    encodings: Record<string, FileEncoding>; // file path -> encoding
+}
+
+export type FileEventKind = 'Modified' | 'Created' | 'Deleted' | 'Unknown';
+
+export interface ResxFileEvent {
+    path: string;
+    kind: FileEventKind;
 }